@@ -0,0 +1,254 @@
+//! Home of the [`AbSlots`](AbSlots) backend.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, FileBackedStorage, Storage};
+use super::errors::{ReadError, WriteError};
+
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Read, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+
+use generic_array::{ArrayLength, GenericArray};
+
+const SLOT_A: &str = "slot_a.bin";
+const SLOT_B: &str = "slot_b.bin";
+const META: &str = "slot_meta.bin";
+
+/// Metadata is the magic bytes, followed by the active slot (0 for A, 1 for
+/// B), followed by the boot-success flag.
+const MAGIC: [u8; 2] = *b"AB";
+
+/// One of the two slots in an [`AbSlots`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl Slot {
+    /// The other slot.
+    pub fn other(self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+}
+
+/// A pair of file backed storage mediums (see [`FileBackedStorage`]) used as
+/// A/B slots for over-the-air updates.
+///
+/// The slots and a small metadata file (recording the active slot and whether
+/// it has booted successfully) live together in one directory. Sector reads
+/// and writes made through [`Storage`] go to the active slot; updates are
+/// staged by writing to the inactive slot (see
+/// [`inactive_mut`](AbSlots::inactive_mut)) and then switched to with
+/// [`commit`](AbSlots::commit).
+///
+/// Metadata updates are atomic: the new metadata is written to a temporary
+/// file which is then renamed over the old one, so a crash leaves either the
+/// old or the new active slot in place, never something in between.
+///
+/// ```rust
+/// # use storage_traits::{AbSlots, Slot, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # let dir = std::env::temp_dir().join("storage-traits-ab-slots");
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// let mut slots = AbSlots::<u8, U4>::new(&dir, 2).unwrap();
+/// assert_eq!(slots.active(), Slot::A);
+/// slots.write_sector(0, &GenericArray::from([1; 4])).unwrap();
+///
+/// // Stage an update to B:
+/// slots.inactive_mut().write_sector(0, &GenericArray::from([2; 4])).unwrap();
+///
+/// let mut buf = GenericArray::default();
+/// slots.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([1; 4]));
+///
+/// // Switch over:
+/// slots.commit().unwrap();
+/// assert_eq!(slots.active(), Slot::B);
+/// assert!(!slots.boot_successful());
+/// slots.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([2; 4]));
+///
+/// // The switch persists:
+/// drop(slots);
+/// let mut slots = AbSlots::<u8, U4>::open(&dir).unwrap();
+/// assert_eq!(slots.active(), Slot::B);
+///
+/// // And can be undone if B doesn't boot:
+/// slots.rollback().unwrap();
+/// assert_eq!(slots.active(), Slot::A);
+/// assert!(slots.boot_successful());
+/// slots.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([1; 4]));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct AbSlots<
+    Word = u8,
+    SECTOR_SIZE = crate::sizes::DEFAULT_SECTOR,
+>
+where
+    Word: AsBytes,
+    SECTOR_SIZE: ArrayLength<Word>,
+{
+    dir: PathBuf,
+    a: FileBackedStorage<Word, SECTOR_SIZE>,
+    b: FileBackedStorage<Word, SECTOR_SIZE>,
+    active: Slot,
+    boot_successful: bool,
+}
+
+impl<W: AsBytes, S: ArrayLength<W>> AbSlots<W, S> {
+    /// Creates a pair of `size_in_sectors` sector slots (and their metadata)
+    /// in `dir`, with slot A active and marked as booted successfully.
+    ///
+    /// `dir` must already exist. Fails if any of the files already exist.
+    pub fn new<P: AsRef<Path>>(dir: P, size_in_sectors: usize) -> IoResult<Self> {
+        let dir = dir.as_ref();
+        let a = FileBackedStorage::new(dir.join(SLOT_A), size_in_sectors)?;
+        let b = FileBackedStorage::new(dir.join(SLOT_B), size_in_sectors)?;
+
+        let slots = Self {
+            dir: dir.to_path_buf(),
+            a,
+            b,
+            active: Slot::A,
+            boot_successful: true,
+        };
+        slots.write_meta(Slot::A, true)?;
+
+        Ok(slots)
+    }
+
+    /// Opens the slots (and metadata) previously created in `dir` with
+    /// [`new`](AbSlots::new).
+    ///
+    /// Fails if the slots aren't the same size or if the metadata is corrupt.
+    pub fn open<P: AsRef<Path>>(dir: P) -> IoResult<Self> {
+        let dir = dir.as_ref();
+        let a = FileBackedStorage::from_file(dir.join(SLOT_A))?;
+        let b = FileBackedStorage::from_file(dir.join(SLOT_B))?;
+
+        if a.capacity() != b.capacity() {
+            return Err(Error::new(ErrorKind::InvalidData, "A/B slots have different sizes"));
+        }
+
+        let mut meta = [0; 4];
+        File::open(dir.join(META))?.read_exact(&mut meta)?;
+
+        let active = match meta {
+            [m0, m1, 0, _] if [m0, m1] == MAGIC => Slot::A,
+            [m0, m1, 1, _] if [m0, m1] == MAGIC => Slot::B,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "corrupt A/B slot metadata")),
+        };
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            a,
+            b,
+            active,
+            boot_successful: meta[3] != 0,
+        })
+    }
+
+    /// The slot that reads and writes currently go to.
+    pub fn active(&self) -> Slot {
+        self.active
+    }
+
+    /// Whether the active slot has been marked as booting successfully (see
+    /// [`mark_boot_successful`](AbSlots::mark_boot_successful)).
+    ///
+    /// This is cleared by [`commit`](AbSlots::commit).
+    pub fn boot_successful(&self) -> bool {
+        self.boot_successful
+    }
+
+    /// The slot that isn't active, for staging an update.
+    pub fn inactive_mut(&mut self) -> &mut FileBackedStorage<W, S> {
+        match self.active {
+            Slot::A => &mut self.b,
+            Slot::B => &mut self.a,
+        }
+    }
+
+    /// Makes the inactive slot the active one and clears the boot-success
+    /// flag, until [`mark_boot_successful`](AbSlots::mark_boot_successful) is
+    /// called.
+    pub fn commit(&mut self) -> IoResult<()> {
+        self.set(self.active.other(), false)
+    }
+
+    /// Switches back to the inactive slot (i.e. the slot that was active
+    /// before the last [`commit`](AbSlots::commit)), marking it as booted
+    /// successfully.
+    pub fn rollback(&mut self) -> IoResult<()> {
+        self.set(self.active.other(), true)
+    }
+
+    /// Records that the active slot booted successfully.
+    pub fn mark_boot_successful(&mut self) -> IoResult<()> {
+        self.set(self.active, true)
+    }
+
+    fn set(&mut self, active: Slot, boot_successful: bool) -> IoResult<()> {
+        self.write_meta(active, boot_successful)?;
+
+        self.active = active;
+        self.boot_successful = boot_successful;
+        Ok(())
+    }
+
+    fn write_meta(&self, active: Slot, boot_successful: bool) -> IoResult<()> {
+        let meta = [MAGIC[0], MAGIC[1], (active == Slot::B) as u8, boot_successful as u8];
+
+        let tmp = self.dir.join(format!("{}.tmp", META));
+        let mut file = File::create(&tmp)?;
+        file.write_all(&meta)?;
+        file.sync_all()?;
+
+        fs::rename(tmp, self.dir.join(META))
+    }
+
+    fn active_mut(&mut self) -> &mut FileBackedStorage<W, S> {
+        match self.active {
+            Slot::A => &mut self.a,
+            Slot::B => &mut self.b,
+        }
+    }
+}
+
+impl<W: AsBytes, S: ArrayLength<W>> Storage for AbSlots<W, S> {
+    type Word = W;
+    type SECTOR_SIZE = S;
+
+    type ReadErr = Error;
+    type WriteErr = Error;
+
+    fn capacity(&self) -> usize {
+        self.a.capacity()
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<W, S>,
+    ) -> Result<(), ReadError<Error>> {
+        self.active_mut().read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<W, S>,
+    ) -> Result<(), WriteError<Error>> {
+        self.active_mut().write_sector(sector_idx, words)
+    }
+}
@@ -0,0 +1,61 @@
+//! Types that wrap a [`Storage`](crate::Storage) implementation and add to or
+//! change its behavior.
+
+mod amplification;
+pub use amplification::*;
+
+mod atomic;
+pub use atomic::*;
+
+mod bad_block;
+pub use bad_block::*;
+
+mod clamped;
+pub use clamped::*;
+
+mod coalescing;
+pub use coalescing::*;
+
+mod erase_before_write;
+pub use erase_before_write::*;
+
+mod partition;
+pub use partition::*;
+
+mod remap_erase;
+pub use remap_erase::*;
+
+mod stripe;
+pub use stripe::*;
+
+mod write_protect;
+pub use write_protect::*;
+
+mod with_oob;
+pub use with_oob::*;
+
+using_std! {
+    mod endurance;
+    pub use endurance::*;
+
+    mod erase_journal;
+    pub use erase_journal::*;
+
+    mod recorder;
+    pub use recorder::*;
+
+    mod wear_sim;
+    pub use wear_sim::*;
+}
+
+#[cfg(feature = "ecc")]
+using_std! {
+    mod ecc;
+    pub use ecc::*;
+}
+
+#[cfg(feature = "compression")]
+using_std! {
+    mod compressed;
+    pub use compressed::*;
+}
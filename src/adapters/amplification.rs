@@ -0,0 +1,190 @@
+//! Home of the [`AmplificationCounter`](AmplificationCounter) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{AsBytes, Storage, WordWritable};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that measures write amplification: the number of
+/// bytes written to the underlying storage medium divided by the number of
+/// bytes that were asked to be written (i.e. to evaluate filesystem designs).
+///
+/// Sector writes are passed through as-is and count the same towards both
+/// totals. This adapter also provides [`WordWritable`] for any storage medium
+/// by doing a read-modify-write of every sector a word write touches (as a
+/// storage medium that only has sector writes would have to); those writes
+/// count their words towards [`logical_bytes_written`] and whole sectors
+/// towards [`physical_bytes_written`]. Only writes that succeed are counted.
+///
+/// [`logical_bytes_written`]: AmplificationCounter::logical_bytes_written
+/// [`physical_bytes_written`]: AmplificationCounter::physical_bytes_written
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, WordWritable, adapters::AmplificationCounter};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U16;
+/// let mut bytes = [0u8; 16 * 4];
+/// let mut storage = AmplificationCounter::new(SliceStorage::<u8, U16>::new(&mut bytes));
+///
+/// storage.write_sector(0, &GenericArray::default()).unwrap();
+/// assert_eq!(storage.amplification_factor(), 1.0);
+///
+/// // Small, unaligned writes have to rewrite whole sectors:
+/// storage.reset_counters();
+/// storage.write_words(5, &[1, 2, 3]).unwrap();
+/// storage.write_words(30, &[4, 5, 6, 7]).unwrap(); // straddles sectors 1 and 2
+///
+/// assert_eq!(storage.logical_bytes_written(), 7);
+/// assert_eq!(storage.physical_bytes_written(), 16 * 3);
+/// assert!(storage.amplification_factor() > 1.0);
+///
+/// drop(storage);
+/// assert_eq!(bytes[30..34], [4, 5, 6, 7]);
+/// ```
+#[derive(Debug)]
+pub struct AmplificationCounter<S: Storage> {
+    inner: S,
+    logical_bytes_written: u64,
+    physical_bytes_written: u64,
+}
+
+impl<S: Storage> AmplificationCounter<S> {
+    /// Wraps `inner`, with both counters starting at zero.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            logical_bytes_written: 0,
+            physical_bytes_written: 0,
+        }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The number of bytes that were asked to be written.
+    pub fn logical_bytes_written(&self) -> u64 {
+        self.logical_bytes_written
+    }
+
+    /// The number of bytes that were written to the underlying storage.
+    pub fn physical_bytes_written(&self) -> u64 {
+        self.physical_bytes_written
+    }
+
+    /// [`physical_bytes_written`](AmplificationCounter::physical_bytes_written)
+    /// divided by
+    /// [`logical_bytes_written`](AmplificationCounter::logical_bytes_written).
+    ///
+    /// This is `1.0` if nothing has been written.
+    pub fn amplification_factor(&self) -> f64 {
+        if self.logical_bytes_written == 0 {
+            return 1.0;
+        }
+
+        self.physical_bytes_written as f64 / self.logical_bytes_written as f64
+    }
+
+    /// Sets both counters back to zero (i.e. to measure a new workload).
+    pub fn reset_counters(&mut self) {
+        self.logical_bytes_written = 0;
+        self.physical_bytes_written = 0;
+    }
+
+    fn sector_bytes() -> u64 {
+        S::sector_size_in_bytes() as u64
+    }
+}
+
+impl<S: Storage> Storage for AmplificationCounter<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        self.inner.write_sector(sector_idx, words)?;
+
+        self.logical_bytes_written += Self::sector_bytes();
+        self.physical_bytes_written += Self::sector_bytes();
+        Ok(())
+    }
+}
+
+impl<S: Storage> WordWritable for AmplificationCounter<S>
+where
+    S::Word: Clone + Default,
+{
+    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>> {
+        self.write_words(addr, &[word])
+    }
+
+    fn write_words(
+        &mut self,
+        word_offset: usize,
+        words: &[Self::Word],
+    ) -> Result<(), WriteError<Self::WriteErr>>
+    where
+        Self::Word: Clone,
+    {
+        match word_offset.checked_add(words.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, words.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        let mut sector = GenericArray::default();
+        let mut written = 0;
+
+        // One read-modify-write per sector touched:
+        while written < words.len() {
+            let offset = word_offset + written;
+            let sector_idx = offset / sector_size;
+            let start = offset % sector_size;
+            let count = (sector_size - start).min(words.len() - written);
+
+            self.inner.read_sector(sector_idx, &mut sector)
+                .map_err(|_| WriteError::ReadFailed { offset: sector_idx })?;
+            sector[start..(start + count)].clone_from_slice(&words[written..(written + count)]);
+            self.inner.write_sector(sector_idx, &sector)?;
+
+            self.logical_bytes_written += (count * <S::Word as AsBytes>::NUM_BYTES) as u64;
+            self.physical_bytes_written += Self::sector_bytes();
+            written += count;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+//! Home of the [`AtomicSector`](AtomicSector) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{AsBytes, PhysicalMapping, Storage};
+use crate::errors::{ReadError, WriteError};
+
+use core::fmt::{self, Debug};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that makes sector writes atomic with respect to power
+/// loss by way of shadow copies.
+///
+/// Every logical sector is backed by two physical sectors (slots); writes go to
+/// whichever slot _isn't_ currently active and then the slot is flipped by
+/// rewriting a commit sector. If the write is interrupted before the commit, the
+/// old contents of the sector are still intact and still active; a sector is
+/// never left torn.
+///
+/// This assumes that writes to the commit sector (physical sector 0) are atomic
+/// (i.e. the commit sector is small enough to be programmed in one operation)
+/// but makes no such assumption about the other sectors.
+///
+/// ## Layout
+///
+/// Physical sector 0 holds one word per logical sector: an all zero word means
+/// the first slot is active and anything else means the second slot is. The
+/// slots for logical sector `n` are physical sectors `1 + 2n` and `2 + 2n`.
+///
+/// This means that this adapter has _over twice_ the space overhead: a backend
+/// with `2n + 1` sectors yields a storage with `n` sectors. The number of
+/// sectors is also capped at the number of words in a sector.
+///
+/// ## Caveats
+///
+/// The contents of the commit sector are cached; this assumes that nothing else
+/// modifies the underlying storage while it's wrapped.
+///
+/// ```rust
+/// # use storage_traits::{Storage, adapters::AtomicSector, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Mem { sectors: [[u8; 4]; 5], writes_until_failure: Option<usize> }
+/// # impl Storage for Mem {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { self.sectors.len() }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.sectors[idx]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         match self.writes_until_failure.as_mut() {
+/// #             Some(0) => return Err(WriteError::Other(())),
+/// #             Some(n) => *n -= 1,
+/// #             None => {},
+/// #         }
+/// #         self.sectors[idx].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// let mut storage = AtomicSector::new(Mem::default()).unwrap();
+/// assert_eq!(storage.capacity(), 2);
+///
+/// storage.write_sector(1, &GenericArray::from([1, 2, 3, 4])).unwrap();
+///
+/// // Simulate losing power after the shadow copy is written but before the
+/// // commit happens:
+/// let mut inner = storage.into_inner();
+/// inner.writes_until_failure = Some(1);
+///
+/// let mut storage = AtomicSector::new(inner).unwrap();
+/// assert!(storage.write_sector(1, &GenericArray::from([5, 6, 7, 8])).is_err());
+///
+/// // The old contents are still there:
+/// let mut storage = AtomicSector::new(storage.into_inner()).unwrap();
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([1, 2, 3, 4]));
+/// ```
+pub struct AtomicSector<S: Storage> {
+    inner: S,
+    commit: GenericArray<S::Word, S::SECTOR_SIZE>,
+    // The commit words for the first and second slot.
+    markers: [S::Word; 2],
+}
+
+impl<S: Storage + Debug> Debug for AtomicSector<S>
+where
+    S::Word: Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AtomicSector")
+            .field("inner", &self.inner)
+            .field("commit", &self.commit.as_slice())
+            .finish()
+    }
+}
+
+impl<S: Storage> AtomicSector<S> {
+    /// Wraps `inner`, reading in its commit sector.
+    ///
+    /// Fails if the commit sector can't be read; storages with no sectors are
+    /// reported as out of range. Word types that can't hold the commit
+    /// markers (a word with all bytes zero and one whose first byte is 1, i.e.
+    /// zero-sized words and words whose [`AsBytes::from`] rejects those) are
+    /// reported as invalid data:
+    ///
+    /// ```rust
+    /// # use storage_traits::{AsBytes, SliceStorage, adapters::AtomicSector, errors::ReadError};
+    /// # use typenum::consts::U4;
+    /// // A word that can't be 1:
+    /// #[derive(Debug, Clone, Default)]
+    /// struct NotOne(u8);
+    ///
+    /// impl AsBytes for NotOne {
+    ///     type To = [u8; 1];
+    ///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
+    ///         match bytes.split_first() {
+    ///             Some((&b, rest)) if b != 1 => Some((NotOne(b), rest)),
+    ///             _ => None,
+    ///         }
+    ///     }
+    ///     fn to(&self) -> [u8; 1] { [self.0] }
+    /// }
+    ///
+    /// let mut words = vec![NotOne(0); 4 * 5];
+    /// assert_eq!(
+    ///     AtomicSector::new(SliceStorage::<NotOne, U4>::new(&mut words)).err(),
+    ///     Some(ReadError::InvalidData { offset: 0 }),
+    /// );
+    ///
+    /// // A word with no bytes at all:
+    /// #[derive(Debug, Clone, Default)]
+    /// struct Empty;
+    ///
+    /// impl AsBytes for Empty {
+    ///     type To = [u8; 0];
+    ///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> { Some((Empty, bytes)) }
+    ///     fn to(&self) -> [u8; 0] { [] }
+    /// }
+    ///
+    /// let mut words = vec![Empty; 4 * 5];
+    /// assert_eq!(
+    ///     AtomicSector::new(SliceStorage::<Empty, U4>::new(&mut words)).err(),
+    ///     Some(ReadError::InvalidData { offset: 0 }),
+    /// );
+    /// ```
+    pub fn new(mut inner: S) -> Result<Self, ReadError<S::ReadErr>>
+    where
+        S::Word: Default,
+    {
+        let mut commit = GenericArray::default();
+        inner.read_sector(0, &mut commit)?;
+
+        let marker = |second_slot: bool| {
+            let mut bytes = S::Word::default().to();
+            for b in bytes.as_mut().iter_mut() {
+                *b = 0;
+            }
+            // Zero-sized words can't tell the two markers apart.
+            *bytes.as_mut().first_mut().ok_or(ReadError::InvalidData { offset: 0 })? = second_slot as u8;
+
+            AsBytes::from(bytes.as_ref())
+                .map(|(word, _)| word)
+                .ok_or(ReadError::InvalidData { offset: 0 })
+        };
+        let markers = [marker(false)?, marker(true)?];
+
+        Ok(Self { inner, commit, markers })
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn second_slot_active(&self, sector_idx: usize) -> bool {
+        self.commit[sector_idx].to().as_ref().iter().any(|b| *b != 0)
+    }
+
+    fn slot_sector(&self, sector_idx: usize, second_slot: bool) -> usize {
+        1 + 2 * sector_idx + (second_slot as usize)
+    }
+
+    fn set_slot(&mut self, sector_idx: usize, second_slot: bool)
+    where
+        S::Word: Clone,
+    {
+        self.commit[sector_idx] = self.markers[second_slot as usize].clone();
+    }
+}
+
+impl<S: Storage> Storage for AtomicSector<S>
+where
+    S::Word: Clone,
+{
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        let pairs = self.inner.capacity().saturating_sub(1) / 2;
+        pairs.min(S::SECTOR_SIZE::to_usize())
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let active = self.second_slot_active(sector_idx);
+        self.inner.read_sector(self.slot_sector(sector_idx, active), buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        // Write the shadow copy:
+        let shadow = !self.second_slot_active(sector_idx);
+        self.inner.write_sector(self.slot_sector(sector_idx, shadow), words)?;
+
+        // And then commit it:
+        self.set_slot(sector_idx, shadow);
+        if let Err(err) = self.inner.write_sector(0, &self.commit) {
+            self.set_slot(sector_idx, !shadow);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: PhysicalMapping> PhysicalMapping for AtomicSector<S>
+where
+    S::Word: Clone,
+{
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        if logical >= self.capacity() {
+            return None;
+        }
+
+        let active = self.second_slot_active(logical);
+        self.inner.physical_sector(self.slot_sector(logical, active))
+    }
+}
@@ -0,0 +1,277 @@
+//! Home of the [`BadBlockTable`](BadBlockTable) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{AsBytes, BadBlockAware, PhysicalMapping, Storage};
+use crate::errors::{ReadError, WriteError};
+
+use core::fmt::{self, Debug};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that keeps a bad block table (see [`BadBlockAware`])
+/// on the storage medium it wraps.
+///
+/// Reads and writes of sectors that have been marked bad fail with
+/// [`ReadError::BadBlock`] and [`WriteError::BadBlock`] without reaching the
+/// underlying storage.
+///
+/// ## Layout
+///
+/// Physical sector 0 holds the table: one word per logical sector, where a word
+/// whose bytes are all `0x00` or all `0xFF` means the sector is good and
+/// anything else means it's bad. Sectors are marked bad with a word whose first
+/// byte is 1 and whose other bytes are 0. Logical sector `n` is physical sector
+/// `1 + n`.
+///
+/// Accepting `0xFF` as good means a freshly erased flash device (see
+/// [`Eraseable::ERASED_WORD`](crate::Eraseable::ERASED_WORD)) starts out with
+/// every sector good, just like a zeroed one.
+///
+/// This means a backend with `n + 1` sectors yields a storage with `n` sectors.
+/// The number of sectors is also capped at the number of words in a sector.
+///
+/// ## Caveats
+///
+/// The table is cached; this assumes that nothing else modifies the underlying
+/// storage while it's wrapped.
+///
+/// ```rust
+/// # use storage_traits::{BadBlockAware, Storage, adapters::BadBlockTable, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Mem([[u8; 4]; 4]);
+/// # impl Storage for Mem {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { self.0.len() }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         self.0[idx].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// let mut storage = BadBlockTable::new(Mem::default()).unwrap();
+/// assert_eq!(storage.capacity(), 3);
+///
+/// storage.mark_bad(1).unwrap();
+/// assert!(storage.is_bad(1));
+///
+/// let data = GenericArray::from([1, 2, 3, 4]);
+/// assert_eq!(storage.write_sector(1, &data), Err(WriteError::BadBlock { offset: 1 }));
+/// storage.write_sector(0, &data).unwrap();
+/// storage.write_sector(2, &data).unwrap();
+///
+/// // The table persists:
+/// let mut storage = BadBlockTable::new(storage.into_inner()).unwrap();
+/// let mut buf = GenericArray::default();
+/// assert_eq!(storage.read_sector(1, &mut buf), Err(ReadError::BadBlock { offset: 1 }));
+/// storage.read_sector(2, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+///
+/// // A freshly erased table marks every sector good:
+/// let storage = BadBlockTable::new(Mem([[0xFF; 4]; 4])).unwrap();
+/// assert!((0..3).all(|idx| !storage.is_bad(idx)));
+/// ```
+pub struct BadBlockTable<S: Storage> {
+    inner: S,
+    table: GenericArray<S::Word, S::SECTOR_SIZE>,
+    bad_marker: S::Word,
+}
+
+impl<S: Storage + Debug> Debug for BadBlockTable<S>
+where
+    S::Word: Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BadBlockTable")
+            .field("inner", &self.inner)
+            .field("table", &self.table.as_slice())
+            .field("bad_marker", &self.bad_marker)
+            .finish()
+    }
+}
+
+impl<S: Storage> BadBlockTable<S> {
+    /// Wraps `inner`, reading in its bad block table.
+    ///
+    /// Fails if the table sector can't be read; storages with no sectors are
+    /// reported as out of range. Word types that can't hold the bad marker
+    /// (i.e. zero-sized words and words whose [`AsBytes::from`] rejects a
+    /// first byte of 1 followed by zeros) are reported as invalid data:
+    ///
+    /// ```rust
+    /// # use storage_traits::{AsBytes, SliceStorage, adapters::BadBlockTable, errors::ReadError};
+    /// # use typenum::consts::U4;
+    /// // A word that can't be 1:
+    /// #[derive(Debug, Clone, Default)]
+    /// struct NotOne(u8);
+    ///
+    /// impl AsBytes for NotOne {
+    ///     type To = [u8; 1];
+    ///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
+    ///         match bytes.split_first() {
+    ///             Some((&b, rest)) if b != 1 => Some((NotOne(b), rest)),
+    ///             _ => None,
+    ///         }
+    ///     }
+    ///     fn to(&self) -> [u8; 1] { [self.0] }
+    /// }
+    ///
+    /// let mut words = vec![NotOne(0); 4 * 2];
+    /// assert_eq!(
+    ///     BadBlockTable::new(SliceStorage::<NotOne, U4>::new(&mut words)).err(),
+    ///     Some(ReadError::InvalidData { offset: 0 }),
+    /// );
+    ///
+    /// // A word with no bytes at all:
+    /// #[derive(Debug, Clone, Default)]
+    /// struct Empty;
+    ///
+    /// impl AsBytes for Empty {
+    ///     type To = [u8; 0];
+    ///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> { Some((Empty, bytes)) }
+    ///     fn to(&self) -> [u8; 0] { [] }
+    /// }
+    ///
+    /// let mut words = vec![Empty; 4 * 2];
+    /// assert_eq!(
+    ///     BadBlockTable::new(SliceStorage::<Empty, U4>::new(&mut words)).err(),
+    ///     Some(ReadError::InvalidData { offset: 0 }),
+    /// );
+    /// ```
+    pub fn new(mut inner: S) -> Result<Self, ReadError<S::ReadErr>>
+    where
+        S::Word: Default,
+    {
+        let mut table = GenericArray::default();
+        inner.read_sector(0, &mut table)?;
+
+        let mut bytes = S::Word::default().to();
+        for b in bytes.as_mut().iter_mut() {
+            *b = 0;
+        }
+        *bytes.as_mut().first_mut().ok_or(ReadError::InvalidData { offset: 0 })? = 1;
+        let bad_marker = AsBytes::from(bytes.as_ref())
+            .map(|(word, _)| word)
+            .ok_or(ReadError::InvalidData { offset: 0 })?;
+
+        Ok(Self { inner, table, bad_marker })
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Storage> Storage for BadBlockTable<S>
+where
+    S::Word: Clone,
+{
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner
+            .capacity()
+            .saturating_sub(1)
+            .min(S::SECTOR_SIZE::to_usize())
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        if self.is_bad(sector_idx) {
+            return Err(ReadError::BadBlock { offset: sector_idx });
+        }
+
+        self.inner.read_sector(1 + sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        if self.is_bad(sector_idx) {
+            return Err(WriteError::BadBlock { offset: sector_idx });
+        }
+
+        self.inner.write_sector(1 + sector_idx, words)
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        if sector_idx >= self.capacity() {
+            return None;
+        }
+
+        self.inner.sector_byte_offset(1 + sector_idx)
+    }
+}
+
+impl<S: Storage> BadBlockAware for BadBlockTable<S>
+where
+    S::Word: Clone,
+{
+    fn is_bad(&self, sector_idx: usize) -> bool {
+        self.table
+            .get(sector_idx)
+            .is_some_and(|word| {
+                let bytes = word.to();
+                let bytes = bytes.as_ref();
+                !(bytes.iter().all(|b| *b == 0x00) || bytes.iter().all(|b| *b == 0xFF))
+            })
+    }
+
+    fn mark_bad(&mut self, sector_idx: usize) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let good = core::mem::replace(&mut self.table[sector_idx], self.bad_marker.clone());
+        if let Err(err) = self.inner.write_sector(0, &self.table) {
+            self.table[sector_idx] = good;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: PhysicalMapping> PhysicalMapping for BadBlockTable<S>
+where
+    S::Word: Clone,
+{
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        if logical >= self.capacity() {
+            return None;
+        }
+
+        self.inner.physical_sector(1 + logical)
+    }
+}
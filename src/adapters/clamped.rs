@@ -0,0 +1,136 @@
+//! Home of the [`Clamped`](Clamped) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{Storage, WordReadable};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+
+/// A [`Storage`] adapter whose multi-word reads clamp to the end of the storage
+/// medium instead of erroring.
+///
+/// [`read_words`](WordReadable::read_words) calls that run off the end of the
+/// storage medium read what's there and fill the rest of the buffer with
+/// `Word::default()` (i.e. zeros); reads that start past the end fill the
+/// entire buffer. This is handy for tooling (i.e. hexdumps near the end of a
+/// device) but it _will_ hide out of bounds bugs which is why it's opt-in.
+///
+/// Everything else (single word reads, sector reads, and all writes) is passed
+/// through unchanged and still errors when out of range.
+///
+/// ```rust
+/// # use storage_traits::{Storage, WordReadable, adapters::Clamped, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Mem([u8; 8]);
+/// # impl Storage for Mem {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { 2 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         if idx >= 2 { return Err(WriteError::OutOfRange { requested_offset: idx, max_offset: 2 }); }
+/// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// # impl WordReadable for Mem {
+/// #     fn read_word(&self, offset: usize) -> Result<u8, ReadError<()>> {
+/// #         self.0.get(offset).copied().ok_or(ReadError::OutOfRange { requested_offset: offset, max_offset: 8 })
+/// #     }
+/// # }
+/// let mut storage = Clamped::new(Mem([1, 2, 3, 4, 5, 6, 7, 8]));
+///
+/// let mut buf = [0xFF; 5];
+/// storage.read_words(6, &mut buf).unwrap();
+/// assert_eq!(buf, [7, 8, 0, 0, 0]);
+///
+/// // Writes aren't clamped:
+/// assert!(storage.write_sector(2, &GenericArray::default()).is_err());
+/// ```
+#[derive(Debug)]
+pub struct Clamped<S: Storage> {
+    inner: S,
+}
+
+impl<S: Storage> Clamped<S> {
+    /// Wraps `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Storage> Storage for Clamped<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        self.inner.write_sector(sector_idx, words)
+    }
+}
+
+impl<S: WordReadable> WordReadable for Clamped<S>
+where
+    S::Word: Default,
+{
+    fn read_word(&self, word_offset: usize) -> Result<Self::Word, ReadError<Self::ReadErr>> {
+        self.inner.read_word(word_offset)
+    }
+
+    fn read_alignment(&self) -> usize {
+        self.inner.read_alignment()
+    }
+
+    fn read_words(
+        &mut self,
+        word_offset: usize,
+        buffer: &mut [Self::Word],
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        let available = self.inner.capacity_in_words().saturating_sub(word_offset);
+        let (valid, past_the_end) = buffer.split_at_mut(available.min(buffer.len()));
+
+        if !valid.is_empty() {
+            self.inner.read_words(word_offset, valid)?;
+        }
+
+        for word in past_the_end.iter_mut() {
+            *word = Default::default();
+        }
+
+        Ok(())
+    }
+}
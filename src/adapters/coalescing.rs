@@ -0,0 +1,237 @@
+//! Home of the [`Coalescing`](Coalescing) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{Storage, WordWritable};
+use crate::errors::{read_error_into_write, ReadError, WriteError};
+
+use core::fmt::{self, Debug};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that gathers word writes to the same sector into one
+/// sector write.
+///
+/// This gives any storage medium [`WordWritable`]. The first word write to a
+/// sector reads the sector in; subsequent word writes to the same sector only
+/// modify the buffered copy. The buffered sector is written out when a word in
+/// a different sector is written and on
+/// [`flush_words`](WordWritable::flush_words). Reads of the buffered sector are
+/// served from the buffer so they never see stale data.
+///
+/// Failed reads of a sector are turned into write errors with
+/// [`read_error_into_write`]; sectors that have never been written to (i.e.
+/// [`ReadError::Uninitialized`]) start out filled with `Word::default()`.
+///
+/// ## Caveats
+///
+/// Pending writes are _not_ written out when this adapter is dropped; call
+/// [`flush_words`](WordWritable::flush_words) (or
+/// [`into_inner`](Coalescing::into_inner)) first.
+///
+/// ```rust
+/// # use storage_traits::{Storage, WordWritable, adapters::Coalescing, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U512;
+/// # #[derive(Debug)]
+/// # struct Mem { bytes: Vec<u8>, sector_writes: usize }
+/// # impl Storage for Mem {
+/// #     type Word = u8; type SECTOR_SIZE = U512; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { self.bytes.len() / 512 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U512>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.bytes[idx * 512..][..512]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U512>) -> Result<(), WriteError<()>> {
+/// #         self.sector_writes += 1;
+/// #         self.bytes[idx * 512..][..512].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// let mut storage = Coalescing::new(Mem { bytes: vec![0; 2 * 512], sector_writes: 0 });
+///
+/// for offset in 512..1024 {
+///     storage.write_word(offset, offset as u8).unwrap();
+/// }
+///
+/// // Reads see the pending writes:
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf[7], 7);
+///
+/// storage.flush_words().unwrap();
+///
+/// let inner = storage.into_inner().unwrap();
+/// assert_eq!(inner.sector_writes, 1);
+/// assert_eq!(inner.bytes[512 + 7], 7);
+/// ```
+///
+/// Word writes to uninitialized sectors:
+///
+/// ```rust
+/// # use storage_traits::{Storage, WordWritable, adapters::Coalescing, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// // A storage medium whose sectors are `None` until they're written to:
+/// # #[derive(Debug)]
+/// struct Lazy([Option<[u8; 4]>; 2]);
+/// # impl Storage for Lazy {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { 2 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx].ok_or(ReadError::Uninitialized { offset: idx * 4 })?); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         self.0[idx] = Some([words[0], words[1], words[2], words[3]]); Ok(())
+/// #     }
+/// # }
+///
+/// let mut storage = Coalescing::new(Lazy([None; 2]));
+/// storage.write_word(5, 9).unwrap();
+///
+/// let inner = storage.into_inner().unwrap();
+/// assert_eq!(inner.0[1], Some([0, 9, 0, 0]));
+/// ```
+pub struct Coalescing<S: Storage> {
+    inner: S,
+    /// The index of the buffered sector and its (modified) contents.
+    pending: Option<(usize, Sector<S>)>,
+}
+
+type Sector<S> = GenericArray<<S as Storage>::Word, <S as Storage>::SECTOR_SIZE>;
+
+impl<S: Storage + Debug> Debug for Coalescing<S>
+where
+    S::Word: Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Coalescing")
+            .field("inner", &self.inner)
+            .field("pending", &self.pending.as_ref().map(|(idx, sector)| (idx, sector.as_slice())))
+            .finish()
+    }
+}
+
+impl<S: Storage> Coalescing<S> {
+    /// Wraps `inner`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            pending: None,
+        }
+    }
+
+    /// Writes out any pending words and then unwraps this adapter, returning
+    /// the underlying storage.
+    pub fn into_inner(mut self) -> Result<S, WriteError<S::WriteErr>> {
+        self.flush_pending()?;
+        Ok(self.inner)
+    }
+
+    /// The index of the sector that has buffered writes, if any.
+    pub fn pending_sector(&self) -> Option<usize> {
+        self.pending.as_ref().map(|(idx, _)| *idx)
+    }
+
+    fn flush_pending(&mut self) -> Result<(), WriteError<S::WriteErr>> {
+        if let Some((sector_idx, sector)) = self.pending.take() {
+            if let Err(err) = self.inner.write_sector(sector_idx, &sector) {
+                self.pending = Some((sector_idx, sector));
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Storage> Storage for Coalescing<S>
+where
+    S::Word: Clone,
+{
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        // Reads of the buffered sector are served from the buffer, pending
+        // writes and all.
+        match &self.pending {
+            Some((idx, sector)) if *idx == sector_idx => {
+                buffer.clone_from_slice(sector);
+                Ok(())
+            }
+            _ => self.inner.read_sector(sector_idx, buffer),
+        }
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        // The whole sector is being replaced so pending words for it are moot.
+        if self.pending_sector() == Some(sector_idx) {
+            self.pending = None;
+        }
+
+        self.inner.write_sector(sector_idx, words)
+    }
+}
+
+impl<S: Storage> WordWritable for Coalescing<S>
+where
+    S::Word: Clone + Default,
+{
+    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>> {
+        if addr >= self.capacity_in_words() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: addr,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        let sector_idx = addr / S::SECTOR_SIZE::to_usize();
+        if self.pending_sector() != Some(sector_idx) {
+            self.flush_pending()?;
+
+            // Sectors that were never written to start out as default words.
+            let mut sector = GenericArray::default();
+            if let Err(err) = self.inner.read_sector(sector_idx, &mut sector) {
+                if let Some(err) = read_error_into_write(err, sector_idx) {
+                    return Err(err);
+                }
+
+                sector = GenericArray::default();
+            }
+            self.pending = Some((sector_idx, sector));
+        }
+
+        if let Some((_, sector)) = self.pending.as_mut() {
+            sector[addr % S::SECTOR_SIZE::to_usize()] = word;
+        }
+
+        Ok(())
+    }
+
+    fn flush_words(&mut self) -> Result<(), WriteError<Self::WriteErr>> {
+        self.flush_pending()
+    }
+}
@@ -0,0 +1,458 @@
+//! Home of the [`Compressed`](Compressed) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::Storage;
+use crate::errors::{read_error_into_write, CompressError, ReadError, WriteError};
+use crate::storage_ext::read_for_write;
+
+use core::convert::{TryFrom, TryInto};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// Number of bytes in an index entry: the offset of the sector's compressed
+/// bytes in the data region followed by their length (both `u32`s, little
+/// endian). A length of 0 means the sector hasn't been written to.
+const ENTRY_BYTES: usize = 8;
+
+/// A compression scheme for [`Compressed`].
+///
+/// This crate only ships [`Rle`]; general purpose compressors (i.e.
+/// `lz4_flex` or `miniz_oxide`) can be plugged in by implementing this.
+pub trait Codec {
+    /// Appends the compressed form of `input` to `out`.
+    fn compress(&self, input: &[u8], out: &mut Vec<u8>);
+
+    /// Appends the decompressed form of `input` to `out`, returning `false` if
+    /// `input` isn't valid compressed data.
+    fn decompress(&self, input: &[u8], out: &mut Vec<u8>) -> bool;
+}
+
+/// A run-length encoding [`Codec`]: runs of up to 255 identical bytes are
+/// stored as a count byte followed by the repeated byte.
+///
+/// This is very cheap and does well on sectors that are mostly one value
+/// (i.e. erased or zeroed space) but _doubles_ the size of data with no runs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rle;
+
+impl Codec for Rle {
+    fn compress(&self, input: &[u8], out: &mut Vec<u8>) {
+        let mut rest = input;
+
+        while let Some(&byte) = rest.first() {
+            let run = rest.iter().take(255).take_while(|b| **b == byte).count();
+
+            out.push(run as u8);
+            out.push(byte);
+            rest = &rest[run..];
+        }
+    }
+
+    fn decompress(&self, input: &[u8], out: &mut Vec<u8>) -> bool {
+        if !input.len().is_multiple_of(2) {
+            return false;
+        }
+
+        for pair in input.chunks(2) {
+            if pair[0] == 0 {
+                return false;
+            }
+
+            out.extend(core::iter::repeat_n(pair[1], pair[0] as usize));
+        }
+
+        true
+    }
+}
+
+/// A [`Storage`] adapter that transparently compresses each sector (with a
+/// pluggable [`Codec`]) so that compressible data takes up less of the
+/// underlying storage.
+///
+/// ## Layout
+///
+/// The underlying storage starts with an index that has an entry (8 bytes) for
+/// every logical sector; the rest of it holds the compressed sectors, packed
+/// one after another. Writes append the new compressed data after the last
+/// sector and update the sector's index entry. When there isn't room to
+/// append, the live data is compacted (moved back to the start of the data
+/// region) first.
+///
+/// The number of logical sectors is picked when the adapter is created and
+/// can be larger than the number of sectors in the underlying storage; this
+/// is the point. However, compressed sizes depend on the data: incompressible
+/// data is _larger_ once "compressed" (twice as large, with [`Rle`]), so
+/// backing every logical sector in the worst case needs more space than the
+/// logical sectors themselves. Writes that wouldn't fit even after compaction
+/// fail with [`CompressError::Full`] before anything is moved, leaving the
+/// sector's old contents in place.
+///
+/// Index entries hold `u32`s so only the first 4 GiB of the data region are
+/// used.
+///
+/// ## Caveats
+///
+/// The index is cached and nothing here is crash safe: an interrupted write
+/// or compaction can leave the index pointing at the wrong data. Compaction
+/// also reads every live sector into memory.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, adapters::Compressed};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U512;
+/// let mut bytes = vec![0u8; 512 * 4];
+/// let mut storage = Compressed::new(SliceStorage::<u8, U512>::new(&mut bytes), 16).unwrap();
+/// assert_eq!(storage.capacity(), 16);
+///
+/// let data = GenericArray::clone_from_slice(&[0x41; 512]);
+/// storage.write_sector(9, &data).unwrap();
+/// assert!(storage.physical_bytes_used() < 512);
+///
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(9, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+///
+/// // Writes that don't fit leave the sector's old contents alone:
+/// let mut noise = GenericArray::default();
+/// for (idx, b) in noise.iter_mut().enumerate() { *b = idx as u8; }
+/// storage.write_sector(1, &noise).unwrap();
+/// assert!(storage.write_sector(9, &noise).is_err());
+/// storage.read_sector(9, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+///
+/// // The index is persisted:
+/// let mut storage = Compressed::new(storage.into_inner(), 16).unwrap();
+/// storage.read_sector(9, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+/// ```
+#[derive(Debug)]
+pub struct Compressed<S: Storage<Word = u8>, C: Codec = Rle> {
+    inner: S,
+    codec: C,
+    // (offset into the data region, length) for every logical sector.
+    index: Vec<(usize, usize)>,
+    next_free: usize,
+}
+
+impl<S: Storage<Word = u8>> Compressed<S, Rle> {
+    /// Wraps `inner`, exposing `logical_sectors` sectors compressed with
+    /// [`Rle`]; see [`with_codec`](Compressed::with_codec).
+    pub fn new(inner: S, logical_sectors: usize) -> Result<Self, ReadError<S::ReadErr>> {
+        Self::with_codec(inner, logical_sectors, Rle)
+    }
+}
+
+impl<S: Storage<Word = u8>, C: Codec> Compressed<S, C> {
+    /// Wraps `inner`, exposing `logical_sectors` sectors compressed with
+    /// `codec`, and reads in the index.
+    ///
+    /// Fails if the index can't be read or if it doesn't fit in `inner` (this
+    /// is reported as out of range). Index entries that point outside of the
+    /// data region are reported as invalid data.
+    ///
+    /// Index entries are packed back to back, so sector sizes that aren't a
+    /// multiple of 8 bytes work too:
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, SparseStorage, Storage, adapters::Compressed};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::{U4, U12};
+    /// let mut bytes = vec![0u8; 4 * 16];
+    /// let mut storage = Compressed::new(SliceStorage::<u8, U4>::new(&mut bytes), 3).unwrap();
+    /// storage.write_sector(2, &GenericArray::from([7; 4])).unwrap();
+    ///
+    /// // Entry 2 straddles index sectors 4 and 5:
+    /// let mut storage = Compressed::new(storage.into_inner(), 3).unwrap();
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(2, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([7; 4]));
+    ///
+    /// // Storage mediums that report never-written sectors as uninitialized
+    /// // work too:
+    /// let mut storage = Compressed::new(SparseStorage::<u8, U4>::new(16), 3).unwrap();
+    /// storage.write_sector(0, &GenericArray::from([1, 2, 3, 4])).unwrap();
+    /// storage.read_sector(0, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([1, 2, 3, 4]));
+    ///
+    /// let mut bytes = vec![0u8; 12 * 4];
+    /// let mut storage = Compressed::new(SliceStorage::<u8, U12>::new(&mut bytes), 2).unwrap();
+    /// storage.write_sector(1, &GenericArray::from([9; 12])).unwrap();
+    /// let mut storage = Compressed::new(storage.into_inner(), 2).unwrap();
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(1, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([9; 12]));
+    /// ```
+    pub fn with_codec(mut inner: S, logical_sectors: usize, codec: C) -> Result<Self, ReadError<S::ReadErr>> {
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        let index_sectors = Self::index_sectors_for(logical_sectors);
+
+        if index_sectors > inner.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: index_sectors,
+                max_offset: inner.capacity(),
+            });
+        }
+
+        let data_bytes = (inner.capacity() - index_sectors) * sector_size;
+        let mut index = Vec::with_capacity(logical_sectors);
+
+        // Entries are packed back to back (see `write_entry`) so they can
+        // straddle sectors when the sector size isn't a multiple of
+        // `ENTRY_BYTES`; read the whole index in before splitting it up.
+        let mut bytes = Vec::with_capacity(index_sectors * sector_size);
+        let mut sector = GenericArray::default();
+        for sector_idx in 0..index_sectors {
+            // Never-written index sectors hold no entries.
+            match inner.read_sector(sector_idx, &mut sector) {
+                Ok(()) => bytes.extend_from_slice(&sector),
+                Err(ReadError::Uninitialized { .. }) => bytes.resize(bytes.len() + sector_size, 0),
+                Err(err) => return Err(err),
+            }
+        }
+
+        for entry in bytes.chunks_exact(ENTRY_BYTES).take(logical_sectors) {
+            let (offset, len) = entry.split_at(4);
+            let offset = u32::from_le_bytes(offset.try_into().unwrap()) as usize;
+            let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+
+            match offset.checked_add(len) {
+                Some(end) if end <= data_bytes => {},
+                _ => return Err(ReadError::InvalidData { offset: index.len() * ENTRY_BYTES }),
+            }
+
+            index.push((offset, len));
+        }
+
+        let next_free = index.iter().map(|(offset, len)| offset + len).max().unwrap_or(0);
+
+        Ok(Self {
+            inner,
+            codec,
+            index,
+            next_free,
+        })
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The number of bytes of the underlying storage's data region that hold
+    /// live (compressed) sectors.
+    pub fn physical_bytes_used(&self) -> usize {
+        self.index.iter().map(|(_, len)| len).sum()
+    }
+
+    fn index_sectors_for(logical_sectors: usize) -> usize {
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        (logical_sectors * ENTRY_BYTES).div_ceil(sector_size)
+    }
+
+    fn data_start(&self) -> usize {
+        Self::index_sectors_for(self.index.len()) * S::SECTOR_SIZE::to_usize()
+    }
+
+    fn data_bytes(&self) -> usize {
+        let max = usize::try_from(u32::MAX).unwrap_or(usize::MAX);
+        (self.inner.capacity_in_bytes() - self.data_start()).min(max)
+    }
+
+    /// Reads `out.len()` bytes starting at byte `offset` of the underlying
+    /// storage.
+    fn read_bytes(&mut self, offset: usize, out: &mut [u8]) -> Result<(), ReadError<S::ReadErr>> {
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        let mut sector = GenericArray::default();
+        let mut done = 0;
+
+        while done < out.len() {
+            let pos = offset + done;
+            let start = pos % sector_size;
+            let count = (sector_size - start).min(out.len() - done);
+
+            self.inner.read_sector(pos / sector_size, &mut sector)?;
+            out[done..(done + count)].copy_from_slice(&sector[start..(start + count)]);
+            done += count;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `bytes` starting at byte `offset` of the underlying storage,
+    /// reading in the sectors that are only partly overwritten.
+    fn write_bytes(&mut self, offset: usize, bytes: &[u8]) -> Result<(), WriteError<S::WriteErr>> {
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        let mut sector = GenericArray::default();
+        let mut done = 0;
+
+        while done < bytes.len() {
+            let pos = offset + done;
+            let sector_idx = pos / sector_size;
+            let start = pos % sector_size;
+            let count = (sector_size - start).min(bytes.len() - done);
+
+            if count != sector_size {
+                let _ = read_for_write(&mut self.inner, sector_idx, &mut sector)?;
+            }
+            sector[start..(start + count)].copy_from_slice(&bytes[done..(done + count)]);
+            self.inner.write_sector(sector_idx, &sector)?;
+            done += count;
+        }
+
+        Ok(())
+    }
+
+    fn write_entry(&mut self, sector_idx: usize) -> Result<(), WriteError<S::WriteErr>> {
+        let (offset, len) = self.index[sector_idx];
+
+        // `data_bytes` keeps both in range; this is a backstop.
+        let too_big = |_| WriteError::OutOfRange {
+            requested_offset: crate::errors::last_offset(offset, len),
+            max_offset: self.data_bytes(),
+        };
+        let mut entry = [0; ENTRY_BYTES];
+        entry[..4].copy_from_slice(&u32::try_from(offset).map_err(too_big)?.to_le_bytes());
+        entry[4..].copy_from_slice(&u32::try_from(len).map_err(too_big)?.to_le_bytes());
+
+        self.write_bytes(sector_idx * ENTRY_BYTES, &entry)
+    }
+
+    /// Moves the live sectors (other than `skip`, which is about to be
+    /// overwritten) to the start of the data region.
+    fn compact(&mut self, skip: usize) -> Result<(), WriteError<S::WriteErr>> {
+        let data_start = self.data_start();
+        let mut live = Vec::new();
+
+        for sector_idx in 0..self.index.len() {
+            let (offset, len) = self.index[sector_idx];
+            if sector_idx == skip || len == 0 {
+                continue;
+            }
+
+            // Live data can't be uninitialized; that's a failed read too.
+            let first_sector = (data_start + offset) / S::SECTOR_SIZE::to_usize();
+            let mut bytes = vec![0; len];
+            self.read_bytes(data_start + offset, &mut bytes).map_err(|err| {
+                read_error_into_write(err, first_sector)
+                    .unwrap_or(WriteError::ReadFailed { offset: first_sector })
+            })?;
+            live.push((sector_idx, bytes));
+        }
+
+        self.index[skip] = (0, 0);
+        self.next_free = 0;
+
+        for (sector_idx, bytes) in live {
+            self.write_bytes(data_start + self.next_free, &bytes)?;
+            self.index[sector_idx] = (self.next_free, bytes.len());
+            self.next_free += bytes.len();
+        }
+
+        for sector_idx in 0..self.index.len() {
+            self.write_entry(sector_idx)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Storage<Word = u8>, C: Codec> Storage for Compressed<S, C> {
+    type Word = u8;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = CompressError<S::WriteErr>;
+
+    fn capacity(&self) -> usize {
+        self.index.len()
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<u8, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let sector_size = S::SECTOR_SIZE::to_usize();
+        let (offset, len) = self.index[sector_idx];
+        if len == 0 {
+            return Err(ReadError::Uninitialized { offset: sector_idx * sector_size });
+        }
+
+        let mut compressed = vec![0; len];
+        let data_start = self.data_start();
+        self.read_bytes(data_start + offset, &mut compressed)?;
+
+        let mut decompressed = Vec::with_capacity(sector_size);
+        if !self.codec.decompress(&compressed, &mut decompressed) || decompressed.len() != sector_size {
+            return Err(ReadError::InvalidData { offset: sector_idx * sector_size });
+        }
+
+        buffer.copy_from_slice(&decompressed);
+        Ok(())
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<u8, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let mut compressed = Vec::new();
+        self.codec.compress(words, &mut compressed);
+
+        if self.next_free + compressed.len() > self.data_bytes() {
+            // Compacting drops the sector's old data so only do it if the new
+            // data will fit afterwards.
+            let live = self.physical_bytes_used() - self.index[sector_idx].1;
+            let available = self.data_bytes() - live;
+            if compressed.len() > available {
+                return Err(WriteError::Other(CompressError::Full {
+                    needed: compressed.len(),
+                    available,
+                }));
+            }
+
+            self.compact(sector_idx).map_err(inner_error)?;
+        }
+
+        let data_start = self.data_start();
+        self.write_bytes(data_start + self.next_free, &compressed).map_err(inner_error)?;
+
+        self.index[sector_idx] = (self.next_free, compressed.len());
+        self.next_free += compressed.len();
+        self.write_entry(sector_idx).map_err(inner_error)
+    }
+}
+
+fn inner_error<E: core::fmt::Debug>(err: WriteError<E>) -> WriteError<CompressError<E>> {
+    use WriteError::*;
+
+    match err {
+        OutOfRange { requested_offset, max_offset } => OutOfRange { requested_offset, max_offset },
+        InvalidNumberOfBytes { bytes_given, bytes_in_a_sector } =>
+            InvalidNumberOfBytes { bytes_given, bytes_in_a_sector },
+        InvalidNumberOfWords { words_given, words_in_a_sector } =>
+            InvalidNumberOfWords { words_given, words_in_a_sector },
+        NotErased { offset } => NotErased { offset },
+        Protected { offset } => Protected { offset },
+        BadBlock { offset } => BadBlock { offset },
+        Worn { offset } => Worn { offset },
+        ReadFailed { offset } => ReadFailed { offset },
+        Other(other) => Other(CompressError::Inner(other)),
+    }
+}
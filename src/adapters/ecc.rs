@@ -0,0 +1,384 @@
+//! Home of the [`EccProtected`](EccProtected) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{decode_words, encode_words, Storage};
+use crate::errors::{ReadError, UpdateError, WriteError};
+
+use core::ops::ControlFlow;
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// Number of bytes of parity stored per sector.
+const PARITY_BYTES: usize = 4;
+
+/// The top bit of the parity holds the overall parity of the sector; the rest
+/// holds the XOR of the scrambled (see `MULTIPLIER`) 1-based positions of all
+/// the set bits in the sector.
+const OVERALL_PARITY_BIT: u32 = 1 << 31;
+
+/// Positions are multiplied by this (mod 2^31) before being XORed together so
+/// that errors that repeat across bytes (i.e. torn writes) don't cancel out.
+/// Since it's odd this is a bijection so single bit errors can still be
+/// located.
+const MULTIPLIER: u32 = 0x9E37_79B1;
+
+/// A [`Storage`] adapter that corrects single bit errors and detects double bit
+/// errors in sectors (SECDED).
+///
+/// Four bytes of parity are kept for every sector; these are stored in sectors
+/// at the end of the underlying storage so the usable capacity (as reported by
+/// [`capacity`](Storage::capacity) and
+/// [`capacity_in_words`](Storage::capacity_in_words)) is reduced accordingly.
+/// For 512 byte sectors this is one parity sector for every 128 data sectors.
+///
+/// Reads of sectors with a single flipped bit return the corrected data and
+/// bump [`corrected`](EccProtected::corrected); reads of sectors with (an even
+/// number of) multiple flipped bits return [`ReadError::Uncorrectable`]. Errors
+/// in the parity itself are reported as uncorrectable.
+///
+/// ## Caveats
+///
+/// The parity is read in when the adapter is constructed and is cached; this
+/// assumes that nothing else modifies the underlying storage while it's
+/// wrapped. The underlying storage should start out zeroed (or every sector
+/// should be written to before it's read) since all zero data is what the
+/// all zero parity matches.
+///
+/// ```rust
+/// # use storage_traits::{BorrowableStorage, SliceStorage, Storage, adapters::EccProtected, errors::ReadError};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U16;
+/// let mut words = [0u8; 16 * 10];
+/// let mut storage = EccProtected::new(SliceStorage::<u8, U16>::new(&mut words)).unwrap();
+/// assert_eq!(storage.capacity(), 8);
+///
+/// let data = GenericArray::from([0x5A; 16]);
+/// let mut buf = GenericArray::default();
+/// storage.write_sector(3, &data).unwrap();
+///
+/// // A single flipped bit is corrected:
+/// storage.inner_mut().sector_mut(3).unwrap()[7] ^= 0b0001_0000;
+/// storage.read_sector(3, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+/// assert_eq!(storage.corrected(), 1);
+///
+/// // Two are detected:
+/// storage.write_sector(5, &data).unwrap();
+/// storage.inner_mut().sector_mut(5).unwrap()[2] ^= 0b1000_0001;
+/// assert_eq!(storage.read_sector(5, &mut buf), Err(ReadError::Uncorrectable { offset: 5 * 16 }));
+/// ```
+#[derive(Debug)]
+pub struct EccProtected<S: Storage> {
+    inner: S,
+    data_sectors: usize,
+    parity: Vec<u8>,
+    corrected: usize,
+}
+
+impl<S: Storage> EccProtected<S> {
+    /// Wraps `inner`, reading in the parity sectors.
+    pub fn new(mut inner: S) -> Result<Self, ReadError<S::ReadErr>>
+    where
+        S::Word: Default,
+    {
+        let per_sector = S::sector_size_in_bytes() / PARITY_BYTES;
+        let total = inner.capacity();
+
+        // The most data sectors we can have such that their parity also fits.
+        let data_sectors = if per_sector == 0 {
+            0
+        } else {
+            total - ((total + per_sector) / (per_sector + 1))
+        };
+
+        let mut parity = Vec::with_capacity((total - data_sectors) * S::sector_size_in_bytes());
+        let mut sector = GenericArray::default();
+        for idx in data_sectors..total {
+            inner.read_sector(idx, &mut sector)?;
+            parity.extend(encode_words(&sector));
+        }
+
+        Ok(Self {
+            inner,
+            data_sectors,
+            parity,
+            corrected: 0,
+        })
+    }
+
+    /// The number of single bit errors that have been corrected so far.
+    pub fn corrected(&self) -> usize {
+        self.corrected
+    }
+
+    /// Mutably borrows the underlying storage.
+    ///
+    /// Writing to the underlying storage directly bypasses the parity; this is
+    /// mostly useful for injecting errors.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Reads every sector and returns the indices of the sectors whose
+    /// contents don't match their parity (i.e. sectors that were torn by an
+    /// interrupted write or that have otherwise been corrupted).
+    ///
+    /// This includes sectors that have errors that _can_ be corrected. Nothing
+    /// is modified (the [`corrected`](EccProtected::corrected) count included).
+    ///
+    /// ```rust
+    /// # use storage_traits::{BorrowableStorage, SliceStorage, Storage, adapters::EccProtected};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U16;
+    /// let mut words = [0u8; 16 * 10];
+    /// let mut storage = EccProtected::new(SliceStorage::<u8, U16>::new(&mut words)).unwrap();
+    /// storage.write_sector(1, &GenericArray::from([0x11; 16])).unwrap();
+    /// storage.write_sector(6, &GenericArray::from([0x66; 16])).unwrap();
+    ///
+    /// // Simulate a write to sector 6 that's interrupted halfway through:
+    /// let mut inner = storage.into_inner();
+    /// inner.sector_mut(6).unwrap()[..8].copy_from_slice(&[0x77; 8]);
+    ///
+    /// let mut storage = EccProtected::new(inner).unwrap();
+    /// assert_eq!(storage.scan_integrity(), Ok(vec![6]));
+    /// ```
+    pub fn scan_integrity(&mut self) -> Result<Vec<usize>, ReadError<S::ReadErr>>
+    where
+        S::Word: Default,
+    {
+        let mut sector = GenericArray::default();
+        let mut corrupted = Vec::new();
+
+        for sector_idx in 0..self.data_sectors {
+            self.inner.read_sector(sector_idx, &mut sector)?;
+
+            if self.syndrome(sector_idx, &encode_words(&sector)) != 0 {
+                corrupted.push(sector_idx);
+            }
+        }
+
+        Ok(corrupted)
+    }
+
+    /// Reads every sector, rewriting the ones with errors that can be
+    /// corrected (so that they don't accumulate into errors that can't be);
+    /// meant to be run periodically on long-lived deployments.
+    ///
+    /// `cb` is called with the index of each sector before it's scrubbed;
+    /// returning [`ControlFlow::Break`] stops the scrub there. Stopping is
+    /// safe: every sector is either untouched or fully rewritten, and the
+    /// report covers the sectors that were scrubbed.
+    ///
+    /// Sectors with errors that can't be corrected are counted, not rewritten;
+    /// other read failures and failed rewrites stop the scrub.
+    ///
+    /// ```rust
+    /// # use storage_traits::{BorrowableStorage, SliceStorage, Storage, adapters::{EccProtected, ScrubReport}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U16;
+    /// # use core::ops::ControlFlow;
+    /// let mut words = [0u8; 16 * 10];
+    /// let mut storage = EccProtected::new(SliceStorage::<u8, U16>::new(&mut words)).unwrap();
+    /// for idx in 0..storage.capacity() {
+    ///     storage.write_sector(idx, &GenericArray::from([idx as u8; 16])).unwrap();
+    /// }
+    ///
+    /// storage.inner_mut().sector_mut(2).unwrap()[4] ^= 0b0000_0100;
+    /// storage.inner_mut().sector_mut(6).unwrap()[9] ^= 0b0011_0000;
+    ///
+    /// // Stopping partway:
+    /// let report = storage.scrub(|idx| if idx < 4 { ControlFlow::Continue(()) } else { ControlFlow::Break(()) });
+    /// assert_eq!(report, Ok(ScrubReport { scrubbed: 4, corrected: 1, uncorrectable: 0 }));
+    /// assert_eq!(storage.scan_integrity(), Ok(vec![6]));
+    ///
+    /// let report = storage.scrub(|_| ControlFlow::Continue(()));
+    /// assert_eq!(report, Ok(ScrubReport { scrubbed: 8, corrected: 0, uncorrectable: 1 }));
+    /// ```
+    pub fn scrub<F: FnMut(usize) -> ControlFlow<()>>(
+        &mut self,
+        mut cb: F,
+    ) -> Result<ScrubReport, UpdateError<S::ReadErr, S::WriteErr>>
+    where
+        S::Word: Default,
+    {
+        let mut report = ScrubReport::default();
+        let mut sector = GenericArray::default();
+
+        for sector_idx in 0..self.data_sectors {
+            if let ControlFlow::Break(()) = cb(sector_idx) {
+                break;
+            }
+
+            let corrected = self.corrected;
+            match self.read_sector(sector_idx, &mut sector) {
+                Ok(()) if self.corrected != corrected => {
+                    self.write_sector(sector_idx, &sector)?;
+                    report.corrected += 1;
+                },
+                Ok(()) => {},
+                Err(ReadError::Uncorrectable { .. }) => report.uncorrectable += 1,
+                Err(err) => return Err(err.into()),
+            }
+
+            report.scrubbed += 1;
+        }
+
+        Ok(report)
+    }
+
+    fn syndrome(&self, sector_idx: usize, bytes: &[u8]) -> u32 {
+        Self::compute_parity(bytes) ^ self.stored_parity(sector_idx)
+    }
+
+    fn stored_parity(&self, sector_idx: usize) -> u32 {
+        let mut bytes = [0; PARITY_BYTES];
+        bytes.copy_from_slice(&self.parity[(sector_idx * PARITY_BYTES)..][..PARITY_BYTES]);
+
+        u32::from_le_bytes(bytes)
+    }
+
+    fn scramble(position: u32) -> u32 {
+        position.wrapping_mul(MULTIPLIER) & !OVERALL_PARITY_BIT
+    }
+
+    fn unscramble(scrambled: u32) -> u32 {
+        // Newton's method for the inverse of an odd number mod 2^32; each step
+        // doubles the number of correct bits.
+        let mut inverse = MULTIPLIER;
+        for _ in 0..5 {
+            inverse = inverse.wrapping_mul(2u32.wrapping_sub(MULTIPLIER.wrapping_mul(inverse)));
+        }
+
+        scrambled.wrapping_mul(inverse) & !OVERALL_PARITY_BIT
+    }
+
+    fn compute_parity(bytes: &[u8]) -> u32 {
+        let mut positions = 0u32;
+        let mut overall = 0u32;
+
+        for (byte_idx, byte) in bytes.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (1 << bit) != 0 {
+                    positions ^= Self::scramble((byte_idx * 8 + bit + 1) as u32);
+                    overall ^= OVERALL_PARITY_BIT;
+                }
+            }
+        }
+
+        positions | overall
+    }
+}
+
+/// The results of an [`EccProtected::scrub`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScrubReport {
+    /// The number of sectors that were read (fewer than the capacity if the
+    /// scrub was stopped early).
+    pub scrubbed: usize,
+    /// The number of sectors that had an error corrected and were rewritten.
+    pub corrected: usize,
+    /// The number of sectors that had errors that couldn't be corrected.
+    pub uncorrectable: usize,
+}
+
+impl<S: Storage> Storage for EccProtected<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.data_sectors
+    }
+
+    // Data sectors are stored as-is, at the same indices.
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        if sector_idx >= self.data_sectors {
+            return None;
+        }
+
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.read_sector(sector_idx, buffer)?;
+
+        let mut bytes = encode_words(buffer);
+        let syndrome = self.syndrome(sector_idx, &bytes);
+        let uncorrectable = ReadError::Uncorrectable {
+            offset: sector_idx * Self::SECTOR_SIZE::to_usize(),
+        };
+
+        if syndrome == 0 {
+            return Ok(());
+        }
+
+        // An odd number of flips; assume it's one and fix it:
+        let position = Self::unscramble(syndrome & !OVERALL_PARITY_BIT) as usize;
+        if syndrome & OVERALL_PARITY_BIT == 0 || position == 0 || position > bytes.len() * 8 {
+            return Err(uncorrectable);
+        }
+
+        let bit = position - 1;
+        bytes[bit / 8] ^= 1 << (bit % 8);
+
+        let (words, _) = decode_words(&bytes);
+        for (word, fixed) in buffer.iter_mut().zip(words) {
+            *word = fixed;
+        }
+
+        self.corrected += 1;
+        Ok(())
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.write_sector(sector_idx, words)?;
+
+        let parity = Self::compute_parity(&encode_words(words));
+        self.parity[(sector_idx * PARITY_BYTES)..][..PARITY_BYTES]
+            .copy_from_slice(&parity.to_le_bytes());
+
+        // Write out the parity sector this sector's parity lives in:
+        let sector_size = S::sector_size_in_bytes();
+        let parity_sector = (sector_idx * PARITY_BYTES) / sector_size;
+        let (parity_words, _) = decode_words(&self.parity[(parity_sector * sector_size)..][..sector_size]);
+
+        match GenericArray::from_exact_iter(parity_words) {
+            Some(parity_words) => self.inner.write_sector(self.data_sectors + parity_sector, &parity_words),
+            None => Err(WriteError::InvalidNumberOfBytes {
+                bytes_given: sector_size,
+                bytes_in_a_sector: sector_size,
+            }),
+        }
+    }
+}
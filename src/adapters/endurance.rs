@@ -0,0 +1,169 @@
+//! Home of the [`Endurance`](Endurance) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{Eraseable, Flash, Storage};
+use crate::errors::{EraseError, ReadError, WriteError};
+
+use generic_array::GenericArray;
+
+/// A [`Storage`] adapter that gives every sector a limited number of
+/// write/erase cycles, to see how the layers above cope with worn out flash.
+///
+/// Every sector write and every erase of a sector (through [`Eraseable`] or
+/// [`Flash`]) uses up one cycle of that sector's budget. Once a sector's
+/// budget is spent, writes and erases of it fail with [`WriteError::Worn`]
+/// without touching the underlying storage; reads keep working. Word writes
+/// through [`Flash`] only program already erased words and don't count.
+///
+/// Unlike [`WearSim`](crate::adapters::WearSim), which degrades the data that's
+/// read back, this models the hard failure at the end of a device's life.
+///
+/// ```rust
+/// # use storage_traits::{SparseStorage, Storage, adapters::Endurance, errors::WriteError};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = Endurance::new(SparseStorage::<u8, U4>::new(4), 3);
+/// let data = GenericArray::from([1; 4]);
+///
+/// for _ in 0..3 {
+///     storage.write_sector(2, &data).unwrap();
+/// }
+/// assert_eq!(storage.remaining_budget(2), Some(0));
+/// assert_eq!(storage.remaining_budget(1), Some(3));
+///
+/// assert_eq!(storage.write_sector(2, &data), Err(WriteError::Worn { offset: 2 }));
+///
+/// // Worn sectors can still be read and other sectors are unaffected:
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(2, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+/// storage.write_sector(1, &data).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Endurance<S: Storage> {
+    inner: S,
+    budget: u64,
+    cycles: Vec<u64>,
+}
+
+impl<S: Storage> Endurance<S> {
+    /// Wraps `inner`, giving each of its sectors `budget` write/erase cycles.
+    pub fn new(inner: S, budget: u64) -> Self {
+        let cycles = vec![0; inner.capacity()];
+
+        Self {
+            inner,
+            budget,
+            cycles,
+        }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The number of write/erase cycles the sector at `sector_idx` has left.
+    ///
+    /// Returns `None` if `sector_idx` isn't in [0, `self.capacity()`).
+    pub fn remaining_budget(&self, sector_idx: usize) -> Option<u64> {
+        self.cycles.get(sector_idx).map(|used| self.budget.saturating_sub(*used))
+    }
+
+    /// The number of write/erase cycles the sector at `sector_idx` has used.
+    pub fn cycles(&self, sector_idx: usize) -> Option<u64> {
+        self.cycles.get(sector_idx).copied()
+    }
+
+    /// Checks that `sector_idx` has a cycle left; doesn't use it up.
+    fn check_budget(&self, sector_idx: usize) -> Result<(), WriteError<S::WriteErr>> {
+        match self.cycles.get(sector_idx) {
+            Some(used) if *used >= self.budget => Err(WriteError::Worn { offset: sector_idx }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<S: Storage> Storage for Endurance<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        self.check_budget(sector_idx)?;
+        self.inner.write_sector(sector_idx, words)?;
+
+        if let Some(used) = self.cycles.get_mut(sector_idx) {
+            *used += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Erases use up a cycle of _every_ sector and fail (before erasing anything)
+/// if any sector is worn.
+impl<S: Eraseable> Eraseable for Endurance<S> {
+    type EraseErr = S::EraseErr;
+
+    const ERASED_WORD: S::Word = S::ERASED_WORD;
+
+    fn erase(&mut self) -> Result<(), EraseError<S::WriteErr, S::EraseErr>> {
+        for sector_idx in 0..self.cycles.len() {
+            self.check_budget(sector_idx).map_err(EraseError::ErrorInIndividualErase)?;
+        }
+
+        self.inner.erase()?;
+
+        for used in self.cycles.iter_mut() {
+            *used += 1;
+        }
+        Ok(())
+    }
+}
+
+// The sectors and words of this adapter are those of `inner`, so callers
+// upholding the contracts of the raw methods here uphold them for `inner`.
+impl<S: Flash> Flash for Endurance<S> {
+    #[allow(unsafe_code)]
+    unsafe fn raw_erase_sector(&mut self, sector_idx: usize) -> Result<(), WriteError<S::WriteErr>> {
+        self.check_budget(sector_idx)?;
+        self.inner.raw_erase_sector(sector_idx)?;
+
+        if let Some(used) = self.cycles.get_mut(sector_idx) {
+            *used += 1;
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn raw_write_word(&mut self, word_offset: usize, word: S::Word) -> Result<(), WriteError<S::WriteErr>> {
+        self.inner.raw_write_word(word_offset, word)
+    }
+}
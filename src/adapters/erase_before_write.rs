@@ -0,0 +1,156 @@
+//! Home of the [`EraseBeforeWrite`](EraseBeforeWrite) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{AsBytes, Eraseable, Flash, Storage};
+use crate::errors::{EraseError, ReadError, WriteError};
+use crate::storage_ext::read_for_write;
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that enforces flash programming rules on sector
+/// writes: writes can only clear bits (1 → 0), never set them, unless the
+/// sector has been erased in between.
+///
+/// Every [`write_sector`](Storage::write_sector) first reads the sector's
+/// current contents and fails with [`WriteError::NotErased`] (for the first
+/// word that would need a bit set) if the new contents aren't reachable by
+/// only clearing bits. This catches the classic bug of reprogramming flash
+/// without erasing it first, on backends (i.e. in-memory ones used in tests)
+/// that would otherwise happily accept the write.
+///
+/// Failed reads are converted with
+/// [`read_error_into_write`](crate::errors::read_error_into_write). Sectors that
+/// have never been written to (i.e. that read as
+/// [`ReadError::Uninitialized`]) have no bits to check, so any write to them
+/// is let through.
+///
+/// Since this costs a read per write, it's opt-in: wrap a storage medium in
+/// this only where the check is wanted. Erases go through the [`Eraseable`]
+/// and [`Flash`] impls, which are forwarded to the underlying storage.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, adapters::EraseBeforeWrite, errors::WriteError};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut words = [0xFFu8; 4 * 2];
+/// let mut storage = EraseBeforeWrite::new(SliceStorage::<u8, U4>::new(&mut words));
+///
+/// // Clearing bits is fine:
+/// storage.write_sector(0, &GenericArray::from([0x00; 4])).unwrap();
+/// storage.write_sector(1, &GenericArray::from([0xF0, 0x0F, 0xFF, 0x00])).unwrap();
+/// storage.write_sector(1, &GenericArray::from([0xF0, 0x00, 0x0F, 0x00])).unwrap();
+///
+/// // Setting them isn't:
+/// assert_eq!(
+///     storage.write_sector(0, &GenericArray::from([0xFF; 4])),
+///     Err(WriteError::NotErased { offset: 0 }),
+/// );
+/// assert_eq!(
+///     storage.write_sector(1, &GenericArray::from([0xF0, 0x00, 0x0F, 0x01])),
+///     Err(WriteError::NotErased { offset: 4 + 3 }),
+/// );
+///
+/// drop(storage);
+/// assert_eq!(words, [0, 0, 0, 0, 0xF0, 0x00, 0x0F, 0x00]);
+/// ```
+#[derive(Debug)]
+pub struct EraseBeforeWrite<S: Storage> {
+    inner: S,
+}
+
+impl<S: Storage> EraseBeforeWrite<S> {
+    /// Wraps `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Storage> Storage for EraseBeforeWrite<S>
+where
+    S::Word: Default,
+{
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        let mut current = GenericArray::default();
+        if !read_for_write(&mut self.inner, sector_idx, &mut current)? {
+            return self.inner.write_sector(sector_idx, words);
+        }
+
+        let sets_bits = |(old, new): &(&S::Word, &S::Word)| {
+            old.to().as_ref().iter().zip(new.to().as_ref()).any(|(o, n)| n & !o != 0)
+        };
+        if let Some(idx) = current.iter().zip(words.iter()).position(|pair| sets_bits(&pair)) {
+            return Err(WriteError::NotErased {
+                offset: sector_idx * S::SECTOR_SIZE::to_usize() + idx,
+            });
+        }
+
+        self.inner.write_sector(sector_idx, words)
+    }
+}
+
+impl<S: Eraseable> Eraseable for EraseBeforeWrite<S>
+where
+    S::Word: Default,
+{
+    type EraseErr = S::EraseErr;
+
+    const ERASED_WORD: S::Word = S::ERASED_WORD;
+
+    fn erase(&mut self) -> Result<(), EraseError<S::WriteErr, S::EraseErr>> {
+        self.inner.erase()
+    }
+}
+
+// The sectors and words of this adapter are those of `inner`, so callers
+// upholding the contracts of the raw methods here uphold them for `inner`.
+impl<S: Flash> Flash for EraseBeforeWrite<S>
+where
+    S::Word: Default,
+{
+    #[allow(unsafe_code)]
+    unsafe fn raw_erase_sector(&mut self, sector_idx: usize) -> Result<(), WriteError<S::WriteErr>> {
+        self.inner.raw_erase_sector(sector_idx)
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn raw_write_word(&mut self, word_offset: usize, word: S::Word) -> Result<(), WriteError<S::WriteErr>> {
+        self.inner.raw_write_word(word_offset, word)
+    }
+}
@@ -0,0 +1,316 @@
+//! Home of the [`EraseJournal`](EraseJournal) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{decode_words, encode_words, Eraseable, Flash, PhysicalMapping, Storage};
+use crate::errors::{EraseError, ReadError, WriteError};
+
+use core::ops::Range;
+use std::convert::TryInto;
+
+use generic_array::{ArrayLength, GenericArray};
+
+/// Marks a journal entry as describing an erase that's in progress; any other
+/// value (i.e. the zeros or erased words a fresh storage medium starts out
+/// with) means there's no erase in progress.
+const IN_PROGRESS: u8 = 0xA5;
+
+/// The flag byte followed by the start and the length of the range (as little
+/// endian `u64`s).
+const ENTRY_BYTES: usize = 1 + 8 + 8;
+
+/// A [`Storage`] adapter that makes multi-sector erases crash safe by
+/// journaling them.
+///
+/// Before [`erase_range`](EraseJournal::erase_range) erases anything it records
+/// the range in a journal sector; once every sector in the range is erased the
+/// journal entry is cleared (by erasing the journal sector). If an erase is interrupted (i.e. by power loss),
+/// [`resume_erase`](EraseJournal::resume_erase) finds the entry and erases the
+/// whole range again. [`Eraseable::erase`] on this adapter is a journaled erase
+/// of every sector.
+///
+/// ## Layout
+///
+/// Physical sector 0 holds the journal: an in-progress flag byte followed by
+/// the start and length of the range as little endian `u64`s. The journal
+/// sector is erased before each entry is written so this works on flash that
+/// can only clear bits between erases. Logical sector
+/// `n` is physical sector `1 + n`; a backend with `n + 1` sectors yields a
+/// storage with `n` sectors. Sectors must be at least 17 bytes long to hold
+/// the journal entry.
+///
+/// Every journaled erase costs two extra erases (and a write) of the journal
+/// sector, so it wears faster than the other sectors.
+///
+/// ```rust
+/// # use storage_traits::{Eraseable, Flash, Storage, adapters::EraseJournal, errors::{EraseError, ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U32;
+/// # #[derive(Debug)]
+/// # struct Nor { bytes: Vec<u8>, power_fails_after: Option<usize> }
+/// # impl Storage for Nor {
+/// #     type Word = u8; type SECTOR_SIZE = U32; type ReadErr = (); type WriteErr = &'static str;
+/// #     fn capacity(&self) -> usize { self.bytes.len() / 32 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U32>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.bytes[idx * 32..][..32]); Ok(())
+/// #     }
+/// #     // Like flash, writes can only clear bits:
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U32>) -> Result<(), WriteError<&'static str>> {
+/// #         for (b, w) in self.bytes[idx * 32..][..32].iter_mut().zip(words) { *b &= w; }
+/// #         Ok(())
+/// #     }
+/// # }
+/// # impl Flash for Nor {
+/// #     unsafe fn raw_erase_sector(&mut self, idx: usize) -> Result<(), WriteError<&'static str>> {
+/// #         match &mut self.power_fails_after {
+/// #             Some(0) => return Err(WriteError::Other("power loss")),
+/// #             Some(n) => *n -= 1,
+/// #             None => {},
+/// #         }
+/// #         self.bytes[idx * 32..][..32].copy_from_slice(&[0xFF; 32]); Ok(())
+/// #     }
+/// #     unsafe fn raw_write_word(&mut self, offset: usize, word: u8) -> Result<(), WriteError<&'static str>> {
+/// #         self.bytes[offset] &= word; Ok(())
+/// #     }
+/// # }
+/// # impl Eraseable for Nor {
+/// #     type EraseErr = ();
+/// #     const ERASED_WORD: u8 = 0xFF;
+/// #     fn erase(&mut self) -> Result<(), EraseError<&'static str, ()>> { unimplemented!() }
+/// # }
+/// let nor = Nor { bytes: vec![0; 32 * 6], power_fails_after: Some(2) };
+/// let mut storage = EraseJournal::new(nor).unwrap();
+/// assert_eq!(storage.capacity(), 5);
+///
+/// // The power goes out partway through:
+/// assert!(storage.erase_range(1, 4).is_err());
+/// assert_eq!(storage.pending_erase(), Ok(Some(1..5)));
+///
+/// // After a "reboot":
+/// let mut nor = storage.into_inner();
+/// nor.power_fails_after = None;
+/// let mut storage = EraseJournal::new(nor).unwrap();
+///
+/// storage.resume_erase().unwrap();
+/// assert_eq!(storage.pending_erase(), Ok(None));
+/// assert_eq!(storage.first_unerased_sector(), Ok(Some(0)));
+///
+/// // Back to back erases each get a fresh journal entry:
+/// storage.erase_range(0, 2).unwrap();
+/// storage.erase_range(2, 3).unwrap();
+/// assert_eq!(storage.pending_erase(), Ok(None));
+/// assert_eq!(storage.verify_erased(), Ok(true));
+/// ```
+#[derive(Debug)]
+pub struct EraseJournal<S: Storage> {
+    inner: S,
+}
+
+impl<S: Storage> EraseJournal<S> {
+    /// Wraps `inner`.
+    ///
+    /// Returns `None` if `inner` has no sectors or if its sectors are too small
+    /// to hold a journal entry.
+    pub fn new(inner: S) -> Option<Self> {
+        if inner.capacity() == 0 || S::sector_size_in_bytes() < ENTRY_BYTES {
+            return None;
+        }
+
+        Some(Self { inner })
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The range of (logical) sectors that an interrupted erase was working
+    /// on, if there is one.
+    pub fn pending_erase(&mut self) -> Result<Option<Range<usize>>, ReadError<S::ReadErr>>
+    where
+        S::Word: Default,
+    {
+        let mut sector = GenericArray::default();
+        self.inner.read_sector(0, &mut sector)?;
+
+        let bytes = encode_words(&sector);
+        if bytes[0] != IN_PROGRESS {
+            return Ok(None);
+        }
+
+        let start = u64::from_le_bytes(bytes[1..9].try_into().unwrap());
+        let len = u64::from_le_bytes(bytes[9..17].try_into().unwrap());
+
+        let range: Option<(usize, usize)> = match (start.try_into(), len.try_into()) {
+            (Ok(start), Ok(len)) => Some((start, len)),
+            _ => None,
+        };
+
+        match range.and_then(|(start, len)| Some(start..start.checked_add(len)?)) {
+            Some(range) => Ok(Some(range)),
+            None => Err(ReadError::InvalidData { offset: 0 }),
+        }
+    }
+}
+
+impl<S: Eraseable + Flash> EraseJournal<S>
+where
+    S::SECTOR_SIZE: ArrayLength<bool>,
+{
+    /// Erases `count` (logical) sectors starting at `start`, journaling the
+    /// erase so that it can be finished with
+    /// [`resume_erase`](EraseJournal::resume_erase) if it's interrupted.
+    ///
+    /// The range is bounds checked before anything is written.
+    pub fn erase_range(&mut self, start: usize, count: usize) -> Result<(), EraseError<S::WriteErr, S::EraseErr>> {
+        match start.checked_add(count) {
+            Some(end) if end <= self.capacity() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(start, count),
+                max_offset: self.capacity(),
+            }.into()),
+        }
+
+        self.write_journal(Some(start..(start + count)))?;
+        self.erase_physical(start..(start + count))?;
+        self.write_journal(None)?;
+
+        Ok(())
+    }
+
+    /// Finishes an erase that was interrupted (see
+    /// [`pending_erase`](EraseJournal::pending_erase)) by erasing its entire
+    /// range again. Does nothing if there's no erase in progress.
+    ///
+    /// Failing to read the journal is reported as a
+    /// [`WriteError::ReadFailed`] for sector 0.
+    pub fn resume_erase(&mut self) -> Result<(), EraseError<S::WriteErr, S::EraseErr>>
+    where
+        S::Word: Default,
+    {
+        let pending = self.pending_erase()
+            .map_err(|_| WriteError::ReadFailed { offset: 0 })?;
+
+        if let Some(range) = pending {
+            if range.end > self.capacity() {
+                return Err(WriteError::ReadFailed { offset: 0 }.into());
+            }
+
+            self.erase_physical(range)?;
+            self.write_journal(None)?;
+        }
+
+        Ok(())
+    }
+
+    // Flash can only clear bits without an erase, so the journal sector is
+    // erased before every update. Clearing the entry is just the erase.
+    fn write_journal(&mut self, entry: Option<Range<usize>>) -> Result<(), WriteError<S::WriteErr>> {
+        let _ = self.inner.erase_sector(0)?;
+
+        let range = match entry {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+
+        let sector_size = S::sector_size_in_bytes();
+        let mut bytes = vec![0; sector_size];
+        bytes[0] = IN_PROGRESS;
+        bytes[1..9].copy_from_slice(&(range.start as u64).to_le_bytes());
+        bytes[9..17].copy_from_slice(&(range.len() as u64).to_le_bytes());
+
+        let (words, _) = decode_words(&bytes);
+        match GenericArray::from_exact_iter(words) {
+            Some(words) => self.inner.write_sector(0, &words),
+            None => Err(WriteError::InvalidNumberOfBytes {
+                bytes_given: sector_size,
+                bytes_in_a_sector: sector_size,
+            }),
+        }
+    }
+
+    fn erase_physical(&mut self, range: Range<usize>) -> Result<(), WriteError<S::WriteErr>> {
+        for sector_idx in range {
+            let _ = self.inner.erase_sector(1 + sector_idx)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Storage> Storage for EraseJournal<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity().saturating_sub(1)
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        if sector_idx >= self.capacity() {
+            return None;
+        }
+
+        self.inner.sector_byte_offset(1 + sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.read_sector(1 + sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.write_sector(1 + sector_idx, words)
+    }
+}
+
+impl<S: Eraseable + Flash> Eraseable for EraseJournal<S>
+where
+    S::SECTOR_SIZE: ArrayLength<bool>,
+{
+    type EraseErr = S::EraseErr;
+
+    const ERASED_WORD: S::Word = S::ERASED_WORD;
+
+    fn erase(&mut self) -> Result<(), EraseError<S::WriteErr, S::EraseErr>> {
+        self.erase_range(0, self.capacity())
+    }
+}
+
+impl<S: PhysicalMapping> PhysicalMapping for EraseJournal<S> {
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        if logical >= self.capacity() {
+            return None;
+        }
+
+        self.inner.physical_sector(1 + logical)
+    }
+}
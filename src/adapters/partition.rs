@@ -0,0 +1,217 @@
+//! Home of the [`Partition`](Partition) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{PhysicalMapping, Storage, WordReadable, WordWritable};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that exposes a window of sectors of the underlying
+/// storage as a storage medium of its own.
+///
+/// Every kind of access (sector reads and writes and word reads and writes) is
+/// translated by the partition's base and checked against the partition's
+/// bounds; nothing can reach outside of the window.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, WordReadable, adapters::Partition};
+/// # use typenum::consts::U4;
+/// let mut words = [0u8; 4 * 8];
+/// for (idx, w) in words.iter_mut().enumerate() { *w = idx as u8; }
+///
+/// let mut part = Partition::new(SliceStorage::<u8, U4>::new(&mut words), 2, 3).unwrap();
+/// assert_eq!(part.capacity(), 3);
+///
+/// // Word offsets are relative to the start of the partition:
+/// assert_eq!(part.read_word(1), Ok(9));
+///
+/// let mut buf = [0; 6];
+/// part.read_words(2, &mut buf).unwrap();
+/// assert_eq!(buf, [10, 11, 12, 13, 14, 15]);
+///
+/// // And the partition's bounds are enforced:
+/// assert!(part.read_word(12).is_err());
+/// assert!(part.read_words(10, &mut buf).is_err());
+///
+/// // Zero-length accesses are fine up to and including the end of the
+/// // partition, even though the underlying storage continues past it:
+/// part.read_words(12, &mut []).unwrap();
+/// assert!(part.read_words(13, &mut []).is_err());
+/// assert!(part.read_words(12, &mut [0]).is_err());
+/// ```
+#[derive(Debug)]
+pub struct Partition<S: Storage> {
+    inner: S,
+    start_sector: usize,
+    size_in_sectors: usize,
+}
+
+impl<S: Storage> Partition<S> {
+    /// Creates a partition spanning `size_in_sectors` sectors of `inner`,
+    /// starting at `start_sector`.
+    ///
+    /// Returns `None` if the partition doesn't fit in `inner`.
+    pub fn new(inner: S, start_sector: usize, size_in_sectors: usize) -> Option<Self> {
+        match start_sector.checked_add(size_in_sectors) {
+            Some(end) if end <= inner.capacity() => Some(Self {
+                inner,
+                start_sector,
+                size_in_sectors,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The index of the sector in the underlying storage that this partition
+    /// starts at.
+    pub fn start_sector(&self) -> usize {
+        self.start_sector
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn base_word(&self) -> usize {
+        self.start_sector * S::SECTOR_SIZE::to_usize()
+    }
+}
+
+impl<S: Storage> Storage for Partition<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.size_in_sectors
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        if sector_idx >= self.size_in_sectors {
+            return None;
+        }
+
+        self.inner.sector_byte_offset(self.start_sector + sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.read_sector(self.start_sector + sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.write_sector(self.start_sector + sector_idx, words)
+    }
+}
+
+impl<S: PhysicalMapping> PhysicalMapping for Partition<S> {
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        if logical >= self.capacity() {
+            return None;
+        }
+
+        self.inner.physical_sector(self.start_sector + logical)
+    }
+}
+
+impl<S: WordReadable> WordReadable for Partition<S> {
+    fn read_word(&self, word_offset: usize) -> Result<Self::Word, ReadError<Self::ReadErr>> {
+        if word_offset >= self.capacity_in_words() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: word_offset,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        self.inner.read_word(self.base_word() + word_offset)
+    }
+
+    fn read_alignment(&self) -> usize {
+        self.inner.read_alignment()
+    }
+
+    fn read_words(
+        &mut self,
+        word_offset: usize,
+        buffer: &mut [Self::Word],
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        match word_offset.checked_add(buffer.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(ReadError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, buffer.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+
+        let base = self.base_word();
+        self.inner.read_words(base + word_offset, buffer)
+    }
+}
+
+impl<S: WordWritable> WordWritable for Partition<S> {
+    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>> {
+        if addr >= self.capacity_in_words() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: addr,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        let base = self.base_word();
+        self.inner.write_word(base + addr, word)
+    }
+
+    fn write_words(
+        &mut self,
+        word_offset: usize,
+        words: &[Self::Word],
+    ) -> Result<(), WriteError<Self::WriteErr>>
+    where
+        Self::Word: Clone,
+    {
+        match word_offset.checked_add(words.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, words.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+
+        let base = self.base_word();
+        self.inner.write_words(base + word_offset, words)
+    }
+
+    fn flush_words(&mut self) -> Result<(), WriteError<Self::WriteErr>> {
+        self.inner.flush_words()
+    }
+}
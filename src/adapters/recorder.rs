@@ -0,0 +1,410 @@
+//! Home of the [`Recorder`](Recorder) adapter and [`replay`](replay).
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{decode_words, encode_words, AsBytes, Eraseable, Flash, Storage};
+use crate::errors::{EraseError, ReadError, ReplayError, WriteError};
+
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+// Op tags. Every op is a tag byte followed by its arguments and then an
+// outcome byte (`OK` or `FAILED`):
+//   - read:         sector index (`u64`, little endian)
+//   - write:        sector index (`u64`, little endian), then the sector's bytes
+//   - erase:        the erased word's bytes
+//   - erase sector: sector index (`u64`, little endian), then the erased word's
+//                   bytes
+//   - write word:   word offset (`u64`, little endian), then the word's bytes
+const READ: u8 = 0;
+const WRITE: u8 = 1;
+const ERASE: u8 = 2;
+const ERASE_SECTOR: u8 = 3;
+const WRITE_WORD: u8 = 4;
+
+const OK: u8 = 0;
+const FAILED: u8 = 1;
+
+/// A [`Storage`] adapter that records every operation made on a storage medium
+/// to a writer so that it can be [`replay`]ed later (i.e. against a fresh
+/// backend, to reproduce a bug).
+///
+/// Sector reads, sector writes, (for [`Eraseable`] storage mediums) erases and
+/// (for [`Flash`] storage mediums that are also [`Eraseable`]) sector erases
+/// and word writes are recorded in the order they're made, _after_ they've been
+/// passed on, along with whether they succeeded. The trace is a compact binary
+/// format: a tag byte per operation followed by its arguments and its outcome,
+/// with sector contents encoded as they would be on disk (see
+/// [`encode_words`]).
+///
+/// Failures to write to the trace don't interrupt the storage operations; the
+/// first one is held on to and returned by [`finish`](Recorder::finish), and
+/// nothing else is recorded after it.
+///
+/// ```rust
+/// # use storage_traits::{SparseStorage, Storage, StorageExt, adapters::{replay, Recorder}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = Recorder::new(SparseStorage::<u16, U4>::new(3), Vec::new());
+///
+/// let mut buf = GenericArray::default();
+/// storage.write_sector(2, &GenericArray::from([1, 2, 3, 4])).unwrap();
+/// storage.write_sector(0, &GenericArray::from([0xAAAA; 4])).unwrap();
+/// storage.write_sector(1, &GenericArray::from([0xBBBB; 4])).unwrap();
+/// storage.read_sector(2, &mut buf).unwrap();
+/// storage.write_sector(2, &GenericArray::from([5, 6, 7, 8])).unwrap();
+///
+/// let (mut original, trace) = storage.finish().unwrap();
+/// assert_eq!(trace.len(), 4 * (1 + 8 + 8 + 1) + (1 + 8 + 1));
+///
+/// let mut fresh = SparseStorage::<u16, U4>::new(3);
+/// assert_eq!(replay(&trace[..], &mut fresh).unwrap(), 5);
+///
+/// let (mut expected, mut actual) = (Vec::new(), Vec::new());
+/// original.read_into_writer(0, 3, &mut expected).unwrap();
+/// fresh.read_into_writer(0, 3, &mut actual).unwrap();
+/// assert_eq!(expected, actual);
+/// ```
+#[derive(Debug)]
+pub struct Recorder<S: Storage, W: Write> {
+    inner: S,
+    sink: W,
+    io_error: Option<io::Error>,
+}
+
+impl<S: Storage, W: Write> Recorder<S, W> {
+    /// Wraps `inner`, recording operations to `sink`.
+    pub fn new(inner: S, sink: W) -> Self {
+        Self {
+            inner,
+            sink,
+            io_error: None,
+        }
+    }
+
+    /// Flushes the trace and unwraps this adapter, returning the underlying
+    /// storage and the sink.
+    ///
+    /// Fails if any part of the trace couldn't be written.
+    pub fn finish(mut self) -> io::Result<(S, W)> {
+        if let Some(err) = self.io_error {
+            return Err(err);
+        }
+
+        self.sink.flush()?;
+        Ok((self.inner, self.sink))
+    }
+
+    fn record<T, E>(&mut self, tag: u8, args: &[&[u8]], res: &Result<T, E>) {
+        if self.io_error.is_some() {
+            return;
+        }
+
+        let outcome = if res.is_ok() { OK } else { FAILED };
+        let res = self.sink.write_all(&[tag])
+            .and_then(|()| args.iter().try_for_each(|arg| self.sink.write_all(arg)))
+            .and_then(|()| self.sink.write_all(&[outcome]));
+
+        if let Err(err) = res {
+            self.io_error = Some(err);
+        }
+    }
+}
+
+impl<S: Storage, W: Write> Storage for Recorder<S, W> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        let res = self.inner.read_sector(sector_idx, buffer);
+        self.record(READ, &[&(sector_idx as u64).to_le_bytes()], &res);
+        res
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        let res = self.inner.write_sector(sector_idx, words);
+        self.record(WRITE, &[&(sector_idx as u64).to_le_bytes(), &encode_words(words)], &res);
+        res
+    }
+}
+
+impl<S: Eraseable, W: Write> Eraseable for Recorder<S, W> {
+    type EraseErr = S::EraseErr;
+
+    const ERASED_WORD: S::Word = S::ERASED_WORD;
+
+    fn erase(&mut self) -> Result<(), EraseError<S::WriteErr, S::EraseErr>> {
+        let res = self.inner.erase();
+        self.record(ERASE, &[S::ERASED_WORD.to().as_ref()], &res);
+        res
+    }
+}
+
+// The sectors and words of this adapter are those of `inner`, so callers
+// upholding the contracts of the raw methods here uphold them for `inner`.
+/// Sector erases and word writes are recorded too. `Eraseable` is needed for
+/// the erased word, which replays of sector erases fill the sector with.
+///
+/// ```rust
+/// # use storage_traits::{Eraseable, Flash, SparseStorage, Storage, adapters::{replay, Recorder}, errors::{EraseError, ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Nor([u8; 8]);
+/// # impl Storage for Nor {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { 2 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// # impl Eraseable for Nor {
+/// #     type EraseErr = ();
+/// #     const ERASED_WORD: u8 = 0xFF;
+/// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 8]; Ok(()) }
+/// # }
+/// # #[allow(unsafe_code)]
+/// # impl Flash for Nor {
+/// #     unsafe fn raw_erase_sector(&mut self, idx: usize) -> Result<(), WriteError<()>> {
+/// #         self.0[idx * 4..][..4].copy_from_slice(&[0xFF; 4]); Ok(())
+/// #     }
+/// #     unsafe fn raw_write_word(&mut self, offset: usize, word: u8) -> Result<(), WriteError<()>> {
+/// #         self.0[offset] = word; Ok(())
+/// #     }
+/// # }
+/// let mut storage = Recorder::new(Nor::default(), Vec::new());
+/// let mut token = storage.erase_sector(1).unwrap();
+/// storage.write_word(&mut token, 6, 0x42).unwrap();
+/// let (_, trace) = storage.finish().unwrap();
+///
+/// let mut fresh = SparseStorage::<u8, U4>::new(2);
+/// assert_eq!(replay(&trace[..], &mut fresh).unwrap(), 2);
+///
+/// let mut buf = GenericArray::default();
+/// fresh.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0xFF, 0xFF, 0x42, 0xFF]));
+/// ```
+impl<S: Flash + Eraseable, W: Write> Flash for Recorder<S, W> {
+    #[allow(unsafe_code)]
+    unsafe fn raw_erase_sector(&mut self, sector_idx: usize) -> Result<(), WriteError<S::WriteErr>> {
+        let res = self.inner.raw_erase_sector(sector_idx);
+        self.record(ERASE_SECTOR, &[&(sector_idx as u64).to_le_bytes(), S::ERASED_WORD.to().as_ref()], &res);
+        res
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn raw_write_word(&mut self, word_offset: usize, word: S::Word) -> Result<(), WriteError<S::WriteErr>> {
+        let bytes = word.to();
+        let res = self.inner.raw_write_word(word_offset, word);
+        self.record(WRITE_WORD, &[&(word_offset as u64).to_le_bytes(), bytes.as_ref()], &res);
+        res
+    }
+}
+
+/// Re-applies a trace produced by a [`Recorder`] to `target`, returning the
+/// number of operations that were replayed.
+///
+/// `target` should have the same word type and sector size as the storage
+/// medium that was recorded. Operations that failed when they were recorded
+/// are skipped (and not counted); the rest are expected to succeed on
+/// `target` too. Reads are reissued (and their errors reported) but their data
+/// is discarded. Erases are replayed by writing the recorded erased word to
+/// every sector (or, for sector erases, to the one sector) and word writes as
+/// read-modify-writes of their sector, so `target` doesn't need to be
+/// [`Eraseable`] or [`Flash`]:
+///
+/// ```rust
+/// # use storage_traits::{Eraseable, SparseStorage, Storage, adapters::{replay, Recorder}, errors::{EraseError, ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Nor([u8; 8]);
+/// # impl Storage for Nor {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { 2 }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         if idx >= 2 { return Err(WriteError::OutOfRange { requested_offset: idx, max_offset: 2 }); }
+/// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// # impl Eraseable for Nor {
+/// #     type EraseErr = ();
+/// #     const ERASED_WORD: u8 = 0xFF;
+/// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 8]; Ok(()) }
+/// # }
+/// let mut storage = Recorder::new(Nor::default(), Vec::new());
+/// storage.write_sector(0, &GenericArray::from([1, 2, 3, 4])).unwrap();
+/// storage.erase().unwrap();
+/// storage.write_sector(1, &GenericArray::from([5, 6, 7, 8])).unwrap();
+///
+/// // Skipped when replaying:
+/// assert!(storage.write_sector(2, &GenericArray::from([9; 4])).is_err());
+/// let (_, trace) = storage.finish().unwrap();
+///
+/// let mut fresh = SparseStorage::<u8, U4>::new(2);
+/// assert_eq!(replay(&trace[..], &mut fresh).unwrap(), 3);
+///
+/// let mut buf = GenericArray::default();
+/// fresh.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0xFF; 4]));
+/// fresh.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([5, 6, 7, 8]));
+///
+/// // Truncated traces are reported:
+/// assert!(replay(&trace[..trace.len() - 1], &mut SparseStorage::<u8, U4>::new(2)).is_err());
+/// ```
+pub fn replay<R: Read, T: Storage>(
+    mut ops: R,
+    target: &mut T,
+) -> Result<usize, ReplayError<T::ReadErr, T::WriteErr>>
+where
+    T::Word: Clone + Default,
+{
+    let mut sector = GenericArray::<T::Word, T::SECTOR_SIZE>::default();
+    let mut sector_bytes = vec![0; T::sector_size_in_bytes()];
+    let mut word_bytes = vec![0; <T::Word as AsBytes>::NUM_BYTES];
+    let (mut op, mut replayed) = (0, 0);
+
+    loop {
+        let mut tag = [0];
+        match ops.read(&mut tag) {
+            Ok(0) => return Ok(replayed),
+            Ok(_) => {},
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(ReplayError::Io(err)),
+        }
+
+        // Every op's arguments are read in full (so the trace stays in sync)
+        // before its outcome decides whether it's reissued.
+        let replayed_op: Op<T::Word> = match tag[0] {
+            READ => Op::Read(read_index(&mut ops, op)?),
+            WRITE => {
+                let sector_idx = read_index(&mut ops, op)?;
+                ops.read_exact(&mut sector_bytes)?;
+                Op::Write(sector_idx)
+            },
+            ERASE => {
+                ops.read_exact(&mut word_bytes)?;
+                Op::Erase(read_word(&word_bytes, op)?)
+            },
+            ERASE_SECTOR => {
+                let sector_idx = read_index(&mut ops, op)?;
+                ops.read_exact(&mut word_bytes)?;
+                Op::EraseSector(sector_idx, read_word(&word_bytes, op)?)
+            },
+            WRITE_WORD => {
+                let word_offset = read_index(&mut ops, op)?;
+                ops.read_exact(&mut word_bytes)?;
+                Op::WriteWord(word_offset, read_word(&word_bytes, op)?)
+            },
+            _ => return Err(ReplayError::Malformed { op }),
+        };
+
+        let mut outcome = [0];
+        ops.read_exact(&mut outcome)?;
+        match outcome[0] {
+            OK => {},
+            FAILED => {
+                op += 1;
+                continue;
+            },
+            _ => return Err(ReplayError::Malformed { op }),
+        }
+
+        match replayed_op {
+            Op::Read(sector_idx) => {
+                target.read_sector(sector_idx, &mut sector).map_err(ReplayError::Read)?;
+            },
+            Op::Write(sector_idx) => {
+                let (words, _) = decode_words(&sector_bytes);
+                let words = GenericArray::from_exact_iter(words)
+                    .ok_or(ReplayError::Malformed { op })?;
+                target.write_sector(sector_idx, &words).map_err(ReplayError::Write)?;
+            },
+            Op::Erase(erased) => {
+                for word in sector.iter_mut() {
+                    *word = erased.clone();
+                }
+                for sector_idx in 0..target.capacity() {
+                    target.write_sector(sector_idx, &sector).map_err(ReplayError::Write)?;
+                }
+            },
+            Op::EraseSector(sector_idx, erased) => {
+                for word in sector.iter_mut() {
+                    *word = erased.clone();
+                }
+                target.write_sector(sector_idx, &sector).map_err(ReplayError::Write)?;
+            },
+            Op::WriteWord(word_offset, word) => {
+                let sector_size = T::SECTOR_SIZE::to_usize();
+                let sector_idx = word_offset / sector_size;
+
+                target.read_sector(sector_idx, &mut sector).map_err(ReplayError::Read)?;
+                sector[word_offset % sector_size] = word;
+                target.write_sector(sector_idx, &sector).map_err(ReplayError::Write)?;
+            },
+        }
+
+        op += 1;
+        replayed += 1;
+    }
+}
+
+// A decoded op, minus the sector contents of writes (which stay in the
+// sector buffer until they're needed).
+enum Op<W> {
+    Read(usize),
+    Write(usize),
+    Erase(W),
+    EraseSector(usize, W),
+    WriteWord(usize, W),
+}
+
+fn read_word<T: AsBytes, E1: core::fmt::Debug, E2: core::fmt::Debug>(
+    bytes: &[u8],
+    op: usize,
+) -> Result<T, ReplayError<E1, E2>> {
+    T::from(bytes).map(|(word, _)| word).ok_or(ReplayError::Malformed { op })
+}
+
+fn read_index<R: Read, E1: core::fmt::Debug, E2: core::fmt::Debug>(
+    ops: &mut R,
+    op: usize,
+) -> Result<usize, ReplayError<E1, E2>> {
+    let mut idx = [0; 8];
+    ops.read_exact(&mut idx)?;
+
+    u64::from_le_bytes(idx).try_into().map_err(|_| ReplayError::Malformed { op })
+}
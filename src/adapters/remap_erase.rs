@@ -0,0 +1,136 @@
+//! Home of the [`RemapErase`](RemapErase) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::Storage;
+use crate::errors::{ReadError, WriteError};
+
+use core::fmt::{self, Debug};
+
+use generic_array::GenericArray;
+
+/// A [`Storage`] adapter that makes a storage medium erased to one value look
+/// like it was erased to another (i.e. a legacy image erased to `0x00` used by
+/// code that expects `0xFF`).
+///
+/// This is a whole-word substitution: words equal to `from_value` are read as
+/// `to_value` and words equal to `to_value` are read as `from_value`. Writes do
+/// the same swap so everything that's written reads back unchanged. Words that
+/// are merely _partly_ made up of the erase value (i.e. `0x00FF` when
+/// remapping `0x0000`) are left alone.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, adapters::RemapErase};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut legacy = [0x00u8; 8];
+/// let mut storage = RemapErase::new(SliceStorage::<u8, U4>::new(&mut legacy), 0x00, 0xFF);
+///
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0xFF; 4]));
+///
+/// // Writes are swapped back into the old convention:
+/// storage.write_sector(1, &GenericArray::from([0xFF, 0x00, 0x12, 0xFF])).unwrap();
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0xFF, 0x00, 0x12, 0xFF]));
+///
+/// drop(storage);
+/// assert_eq!(legacy[4..], [0x00, 0xFF, 0x12, 0x00]);
+/// ```
+pub struct RemapErase<S: Storage> {
+    inner: S,
+    from_value: S::Word,
+    to_value: S::Word,
+}
+
+impl<S: Storage + Debug> Debug for RemapErase<S>
+where
+    S::Word: Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RemapErase")
+            .field("inner", &self.inner)
+            .field("from_value", &self.from_value)
+            .field("to_value", &self.to_value)
+            .finish()
+    }
+}
+
+impl<S: Storage> RemapErase<S>
+where
+    S::Word: PartialEq + Clone,
+{
+    /// Wraps `inner`, presenting words that are `from_value` (the erase value
+    /// the medium was actually erased to) as `to_value`, and vice versa.
+    pub fn new(inner: S, from_value: S::Word, to_value: S::Word) -> Self {
+        Self {
+            inner,
+            from_value,
+            to_value,
+        }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn swap(&self, word: &mut S::Word) {
+        if *word == self.from_value {
+            *word = self.to_value.clone();
+        } else if *word == self.to_value {
+            *word = self.from_value.clone();
+        }
+    }
+}
+
+impl<S: Storage> Storage for RemapErase<S>
+where
+    S::Word: PartialEq + Clone,
+{
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)?;
+
+        for word in buffer.iter_mut() {
+            self.swap(word);
+        }
+
+        Ok(())
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        let mut words = words.clone();
+        for word in words.iter_mut() {
+            self.swap(word);
+        }
+
+        self.inner.write_sector(sector_idx, &words)
+    }
+}
@@ -0,0 +1,204 @@
+//! Home of the [`Stripe`](Stripe) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{Storage, WordReadable};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that interleaves the sectors of two storage mediums
+/// (RAID 0), so that large sequential accesses are spread across both.
+///
+/// Sectors are split into stripes of `stripe_width` sectors that alternate
+/// between `A` and `B`: with a width of 1 (the default), even sectors go to `A`
+/// and odd sectors go to `B`.
+///
+/// The capacity is the sum of the two capacities when they're the same (and
+/// multiples of the stripe width); otherwise only the stripes that can be
+/// alternated are used.
+///
+/// Multi-word reads ([`read_words`](WordReadable::read_words)) are split at
+/// stripe boundaries, with each piece going to the backend that holds it.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, WordReadable, adapters::Stripe};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let (mut a, mut b) = ([0u8; 4 * 2], [0u8; 4 * 2]);
+/// let mut storage = Stripe::new(SliceStorage::<u8, U4>::new(&mut a), SliceStorage::<u8, U4>::new(&mut b));
+/// assert_eq!(storage.capacity(), 4);
+///
+/// storage.write_sector(0, &GenericArray::from([1, 2, 3, 4])).unwrap();
+/// storage.write_sector(1, &GenericArray::from([5, 6, 7, 8])).unwrap();
+/// storage.write_sector(3, &GenericArray::from([9; 4])).unwrap();
+///
+/// // A read spanning sectors 0 and 1 is reassembled from both backends:
+/// let mut buf = [0; 6];
+/// storage.read_words(1, &mut buf).unwrap();
+/// assert_eq!(buf, [2, 3, 4, 5, 6, 7]);
+///
+/// drop(storage);
+/// assert_eq!(a, [1, 2, 3, 4, 0, 0, 0, 0]);
+/// assert_eq!(b, [5, 6, 7, 8, 9, 9, 9, 9]);
+/// ```
+#[derive(Debug)]
+pub struct Stripe<A: Storage, B: Storage> {
+    a: A,
+    b: B,
+    stripe_width: usize,
+}
+
+impl<A, B> Stripe<A, B>
+where
+    A: Storage,
+    B: Storage<Word = A::Word, SECTOR_SIZE = A::SECTOR_SIZE, ReadErr = A::ReadErr, WriteErr = A::WriteErr>,
+{
+    /// Stripes `a` and `b` one sector at a time.
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b, stripe_width: 1 }
+    }
+
+    /// Stripes `a` and `b` `stripe_width` sectors at a time.
+    ///
+    /// Returns `None` if `stripe_width` is 0.
+    pub fn with_stripe_width(a: A, b: B, stripe_width: usize) -> Option<Self> {
+        if stripe_width == 0 {
+            return None;
+        }
+
+        Some(Self { a, b, stripe_width })
+    }
+
+    /// Unwraps this adapter, returning the two underlying storages.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+
+    /// Which backend the sector at `sector_idx` is in (`false` for `A`, `true`
+    /// for `B`) and its index there.
+    fn locate(&self, sector_idx: usize) -> (bool, usize) {
+        let stripe = sector_idx / self.stripe_width;
+        let within = sector_idx % self.stripe_width;
+
+        (stripe % 2 == 1, (stripe / 2) * self.stripe_width + within)
+    }
+}
+
+impl<A, B> Storage for Stripe<A, B>
+where
+    A: Storage,
+    B: Storage<Word = A::Word, SECTOR_SIZE = A::SECTOR_SIZE, ReadErr = A::ReadErr, WriteErr = A::WriteErr>,
+{
+    type Word = A::Word;
+    type SECTOR_SIZE = A::SECTOR_SIZE;
+
+    type ReadErr = A::ReadErr;
+    type WriteErr = A::WriteErr;
+
+    fn capacity(&self) -> usize {
+        let stripes_a = self.a.capacity() / self.stripe_width;
+        let stripes_b = self.b.capacity() / self.stripe_width;
+
+        // Stripes alternate starting with `A`, so `A` can have one more than
+        // `B` but not the other way around.
+        let stripes = if stripes_a > stripes_b { 2 * stripes_b + 1 } else { 2 * stripes_a };
+        stripes * self.stripe_width
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        match self.locate(sector_idx) {
+            (false, idx) => self.a.read_sector(idx, buffer),
+            (true, idx) => self.b.read_sector(idx, buffer),
+        }
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        match self.locate(sector_idx) {
+            (false, idx) => self.a.write_sector(idx, words),
+            (true, idx) => self.b.write_sector(idx, words),
+        }
+    }
+}
+
+impl<A, B> WordReadable for Stripe<A, B>
+where
+    A: WordReadable,
+    B: WordReadable<Word = A::Word, SECTOR_SIZE = A::SECTOR_SIZE, ReadErr = A::ReadErr, WriteErr = A::WriteErr>,
+{
+    fn read_word(&self, word_offset: usize) -> Result<Self::Word, ReadError<Self::ReadErr>> {
+        if word_offset >= self.capacity_in_words() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: word_offset,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        let sector_size = A::SECTOR_SIZE::to_usize();
+        let within = word_offset % sector_size;
+
+        match self.locate(word_offset / sector_size) {
+            (false, idx) => self.a.read_word(idx * sector_size + within),
+            (true, idx) => self.b.read_word(idx * sector_size + within),
+        }
+    }
+
+    fn read_words(
+        &mut self,
+        word_offset: usize,
+        buffer: &mut [Self::Word],
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        match word_offset.checked_add(buffer.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(ReadError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, buffer.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+
+        let sector_size = A::SECTOR_SIZE::to_usize();
+        let stripe_words = self.stripe_width * sector_size;
+
+        // One read per stripe touched; within a stripe the words are
+        // contiguous in the backend that holds it.
+        let mut done = 0;
+        while done < buffer.len() {
+            let offset = word_offset + done;
+            let run = (stripe_words - offset % stripe_words).min(buffer.len() - done);
+            let chunk = &mut buffer[done..(done + run)];
+
+            let within = offset % sector_size;
+            match self.locate(offset / sector_size) {
+                (false, idx) => self.a.read_words(idx * sector_size + within, chunk)?,
+                (true, idx) => self.b.read_words(idx * sector_size + within, chunk)?,
+            }
+
+            done += run;
+        }
+
+        Ok(())
+    }
+}
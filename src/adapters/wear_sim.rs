@@ -0,0 +1,152 @@
+//! Home of the [`WearSim`](WearSim) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{AsBytes, Storage};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that simulates wear by flipping bits on reads.
+///
+/// Every write to a sector counts as an erase cycle for that sector. On reads,
+/// each bit of the sector is flipped with a probability of
+/// `erase_cycles * flip_rate` (capped at 1). The underlying storage is never
+/// modified by this; the flips are applied to the data that's read out.
+///
+/// This is meant for testing error detection/correction layers against
+/// (somewhat) realistic degradation. The random number generator is seeded so
+/// that runs are reproducible.
+///
+/// ```rust
+/// # use storage_traits::{SparseStorage, Storage, adapters::WearSim};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U16;
+/// let mut storage = WearSim::new(SparseStorage::<u8, U16>::new(2), 0xC0FFEE, 1e-4);
+/// let data = GenericArray::from([0x5A; 16]);
+/// let mut buf = GenericArray::default();
+///
+/// storage.write_sector(0, &data).unwrap();
+/// storage.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+///
+/// for _ in 0..10_000 {
+///     storage.write_sector(1, &data).unwrap();
+/// }
+/// assert_eq!(storage.erase_cycles(1), Some(10_000));
+///
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_ne!(buf, data);
+/// ```
+#[derive(Debug)]
+pub struct WearSim<S: Storage> {
+    inner: S,
+    erase_cycles: Vec<u64>,
+    flip_rate: f64,
+    rng_state: u64,
+}
+
+impl<S: Storage> WearSim<S> {
+    /// Wraps `inner`. `flip_rate` is the probability that a bit flips _per
+    /// erase cycle_ of its sector.
+    pub fn new(inner: S, seed: u64, flip_rate: f64) -> Self {
+        let erase_cycles = vec![0; inner.capacity()];
+
+        Self {
+            inner,
+            erase_cycles,
+            flip_rate,
+            // xorshift gets stuck on 0.
+            rng_state: seed | 1,
+        }
+    }
+
+    /// The number of simulated erase cycles the sector at `sector_idx` has
+    /// gone through.
+    pub fn erase_cycles(&self, sector_idx: usize) -> Option<u64> {
+        self.erase_cycles.get(sector_idx).copied()
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    // xorshift64*; good enough for this.
+    fn next_random(&mut self) -> f64 {
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+
+        let val = self.rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (val >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<S: Storage> Storage for WearSim<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)?;
+
+        let cycles = self.erase_cycles(sector_idx).unwrap_or(0);
+        let probability = (cycles as f64 * self.flip_rate).min(1.0);
+        if probability <= 0.0 {
+            return Ok(());
+        }
+
+        for (idx, word) in buffer.iter_mut().enumerate() {
+            let mut bytes = word.to();
+            for byte in bytes.as_mut().iter_mut() {
+                for bit in 0..8 {
+                    if self.next_random() < probability {
+                        *byte ^= 1 << bit;
+                    }
+                }
+            }
+
+            *word = AsBytes::from(bytes.as_ref())
+                .map(|(word, _)| word)
+                .ok_or(ReadError::InvalidData {
+                    offset: sector_idx * Self::SECTOR_SIZE::to_usize() + idx,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        self.inner.write_sector(sector_idx, words)?;
+
+        if let Some(cycles) = self.erase_cycles.get_mut(sector_idx) {
+            *cycles += 1;
+        }
+
+        Ok(())
+    }
+}
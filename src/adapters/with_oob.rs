@@ -0,0 +1,198 @@
+//! Home of the [`WithOob`](WithOob) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{OutOfBand, Storage};
+use crate::errors::{ReadError, WriteError};
+use crate::storage_ext::read_for_write;
+
+use core::marker::PhantomData;
+
+use generic_array::{ArrayLength, GenericArray};
+use typenum::marker_traits::Unsigned;
+
+/// A [`Storage`] adapter that gives any storage medium an [`OutOfBand`] area
+/// of `N` words per sector by setting aside sectors at the end of the
+/// underlying storage to hold them.
+///
+/// Each reserved sector holds the out-of-band areas of
+/// `SECTOR_SIZE / N` (rounded down) sectors, so the usable capacity is
+/// reduced to `capacity * per / (per + 1)` (rounded down) where `per` is that
+/// number; i.e. 8 byte areas for 512 byte sectors cost 1 sector in 65.
+/// Out-of-band areas are written with a read-modify-write of the reserved
+/// sector that holds them; reserved sectors that have never been written to
+/// are treated as holding `Word::default()`s.
+///
+/// ```rust
+/// # use storage_traits::{OutOfBand, SliceStorage, Storage, adapters::WithOob};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::{U2, U8};
+/// let mut words = [0u8; 8 * 10];
+/// let mut storage = WithOob::<_, U2>::new(SliceStorage::<u8, U8>::new(&mut words)).unwrap();
+///
+/// // 4 areas fit in a sector, so 2 of the 10 sectors are set aside:
+/// assert_eq!(storage.capacity(), 8);
+///
+/// storage.write_sector(0, &GenericArray::from([7; 8])).unwrap();
+/// storage.write_oob(0, &GenericArray::from([0xA, 0xB])).unwrap();
+///
+/// let mut oob = GenericArray::default();
+/// storage.read_oob(0, &mut oob).unwrap();
+/// assert_eq!(oob, GenericArray::from([0xA, 0xB]));
+///
+/// // The main data is unaffected:
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([7; 8]));
+///
+/// storage.write_sector(0, &GenericArray::from([9; 8])).unwrap();
+/// storage.read_oob(0, &mut oob).unwrap();
+/// assert_eq!(oob, GenericArray::from([0xA, 0xB]));
+///
+/// assert!(storage.read_oob(8, &mut oob).is_err());
+/// ```
+#[derive(Debug)]
+pub struct WithOob<S: Storage, N> {
+    inner: S,
+    size_in_sectors: usize,
+    _n: PhantomData<N>,
+}
+
+impl<S: Storage, N: ArrayLength<S::Word>> WithOob<S, N> {
+    /// Wraps `inner`, setting aside enough of its sectors to hold an `N` word
+    /// out-of-band area for each of the rest.
+    ///
+    /// Returns `None` if `N` is 0 or is larger than a sector.
+    pub fn new(inner: S) -> Option<Self> {
+        if N::to_usize() == 0 || N::to_usize() > S::SECTOR_SIZE::to_usize() {
+            return None;
+        }
+
+        let per = Self::areas_per_sector();
+        let size_in_sectors = inner.capacity() / (per + 1) * per
+            + (inner.capacity() % (per + 1)).saturating_sub(1);
+
+        Some(Self {
+            inner,
+            size_in_sectors,
+            _n: PhantomData,
+        })
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn areas_per_sector() -> usize {
+        S::SECTOR_SIZE::to_usize() / N::to_usize()
+    }
+
+    /// The sector of `inner` holding the out-of-band area of `sector_idx` and
+    /// the offset of the area in it.
+    fn locate(&self, sector_idx: usize) -> (usize, usize) {
+        let per = Self::areas_per_sector();
+        (self.size_in_sectors + sector_idx / per, (sector_idx % per) * N::to_usize())
+    }
+}
+
+impl<S: Storage, N: ArrayLength<S::Word>> Storage for WithOob<S, N> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.size_in_sectors
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        if sector_idx >= self.size_in_sectors {
+            return None;
+        }
+
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.inner.write_sector(sector_idx, words)
+    }
+}
+
+impl<S: Storage, N: ArrayLength<S::Word>> OutOfBand for WithOob<S, N>
+where
+    S::Word: Clone + Default,
+{
+    type OOB_SIZE = N;
+
+    fn read_oob(
+        &mut self,
+        sector_idx: usize,
+        buf: &mut GenericArray<Self::Word, N>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let (oob_sector, start) = self.locate(sector_idx);
+        let mut sector = GenericArray::default();
+
+        self.inner.read_sector(oob_sector, &mut sector)?;
+        buf.clone_from_slice(&sector[start..(start + N::to_usize())]);
+        Ok(())
+    }
+
+    fn write_oob(
+        &mut self,
+        sector_idx: usize,
+        data: &GenericArray<Self::Word, N>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        let (oob_sector, start) = self.locate(sector_idx);
+        let mut sector = GenericArray::default();
+
+        let _ = read_for_write(&mut self.inner, oob_sector, &mut sector)?;
+        sector[start..(start + N::to_usize())].clone_from_slice(data);
+        self.inner.write_sector(oob_sector, &sector)
+    }
+}
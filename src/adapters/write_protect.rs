@@ -0,0 +1,142 @@
+//! Home of the [`WriteProtect`](WriteProtect) adapter.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{HardwareProtect, Storage, WordWritable};
+use crate::errors::{ReadError, WriteError};
+
+use generic_array::GenericArray;
+
+/// A [`Storage`] adapter that adds a software write protect switch (see
+/// [`HardwareProtect`]) to a storage medium that doesn't have one.
+///
+/// Protection starts off. While it's on, all writes fail with
+/// [`WriteError::Protected`] without reaching the underlying storage.
+///
+/// ```rust
+/// # use storage_traits::{HardwareProtect, Storage, adapters::WriteProtect, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Mem([[u8; 4]; 2]);
+/// # impl Storage for Mem {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+/// #     fn capacity(&self) -> usize { self.0.len() }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+/// #         buf.copy_from_slice(&self.0[idx]); Ok(())
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+/// #         self.0[idx].copy_from_slice(words); Ok(())
+/// #     }
+/// # }
+/// let mut storage = WriteProtect::new(Mem::default());
+/// let data = GenericArray::from([1, 2, 3, 4]);
+///
+/// storage.set_write_protect(true).unwrap();
+/// assert!(storage.is_write_protected());
+/// assert_eq!(storage.write_sector(1, &data), Err(WriteError::Protected { offset: 1 }));
+///
+/// storage.set_write_protect(false).unwrap();
+/// storage.write_sector(1, &data).unwrap();
+/// assert_eq!(storage.into_inner().0[1], [1, 2, 3, 4]);
+/// ```
+#[derive(Debug)]
+pub struct WriteProtect<S: Storage> {
+    inner: S,
+    protected: bool,
+}
+
+impl<S: Storage> WriteProtect<S> {
+    /// Wraps `inner`, with write protection off.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            protected: false,
+        }
+    }
+
+    /// Unwraps this adapter, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Storage> Storage for WriteProtect<S> {
+    type Word = S::Word;
+    type SECTOR_SIZE = S::SECTOR_SIZE;
+
+    type ReadErr = S::ReadErr;
+    type WriteErr = S::WriteErr;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn optimal_chunk_sectors(&self) -> usize {
+        self.inner.optimal_chunk_sectors()
+    }
+
+    fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+        self.inner.sector_byte_offset(sector_idx)
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.inner.read_sector(sector_idx, buffer)
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if self.protected {
+            return Err(WriteError::Protected { offset: sector_idx });
+        }
+
+        self.inner.write_sector(sector_idx, words)
+    }
+}
+
+impl<S: WordWritable> WordWritable for WriteProtect<S> {
+    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>> {
+        if self.protected {
+            return Err(WriteError::Protected { offset: addr });
+        }
+
+        self.inner.write_word(addr, word)
+    }
+
+    fn write_words(
+        &mut self,
+        word_offset: usize,
+        words: &[Self::Word],
+    ) -> Result<(), WriteError<Self::WriteErr>>
+    where
+        Self::Word: Clone,
+    {
+        if self.protected {
+            return Err(WriteError::Protected { offset: word_offset });
+        }
+
+        self.inner.write_words(word_offset, words)
+    }
+
+    fn flush_words(&mut self) -> Result<(), WriteError<Self::WriteErr>> {
+        self.inner.flush_words()
+    }
+}
+
+impl<S: Storage> HardwareProtect for WriteProtect<S> {
+    fn is_write_protected(&self) -> bool {
+        self.protected
+    }
+
+    fn set_write_protect(&mut self, on: bool) -> Result<(), Self::WriteErr> {
+        self.protected = on;
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+//! Conversions between sectors ([`GenericArray`]s) and plain `[Word; N]`
+//! arrays, for interop with const generic code.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use core::marker::PhantomData;
+
+use generic_array::{ArrayLength, GenericArray};
+use typenum::marker_traits::Unsigned;
+
+// Evaluating `SAME` fails to compile when the lengths don't match.
+struct SameLength<S, const N: usize>(PhantomData<S>);
+
+impl<S: Unsigned, const N: usize> SameLength<S, N> {
+    const SAME: () = assert!(
+        S::USIZE == N,
+        "array length does not match the sector size",
+    );
+}
+
+/// Converts a sector into a `[Word; N]`.
+///
+/// `N` must be the sector size; this is checked at compile time.
+///
+/// ```rust
+/// # use storage_traits::{array_to_sector, sector_to_array};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let sector: GenericArray<u16, U4> = array_to_sector([1, 2, 3, 4]);
+/// assert_eq!(sector, GenericArray::from([1, 2, 3, 4]));
+///
+/// let array: [u16; 4] = sector_to_array(sector);
+/// assert_eq!(array, [1, 2, 3, 4]);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use storage_traits::sector_to_array;
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let array: [u16; 5] = sector_to_array(GenericArray::<u16, U4>::default());
+/// ```
+pub fn sector_to_array<W, S: ArrayLength<W>, const N: usize>(
+    sector: GenericArray<W, S>,
+) -> [W; N] {
+    let () = SameLength::<S, N>::SAME;
+
+    let mut words = sector.into_iter();
+    core::array::from_fn(|_| words.next().unwrap())
+}
+
+/// Converts a `[Word; N]` into a sector.
+///
+/// `N` must be the sector size; this is checked at compile time. See
+/// [`sector_to_array`].
+pub fn array_to_sector<W, S: ArrayLength<W>, const N: usize>(
+    array: [W; N],
+) -> GenericArray<W, S> {
+    let () = SameLength::<S, N>::SAME;
+
+    GenericArray::from_exact_iter(array).unwrap()
+}
@@ -0,0 +1,134 @@
+//! Home of the [`ByteStore`](ByteStore) facade.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::Storage;
+use super::errors::{ReadError, UpdateError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A facade that makes a byte addressed storage medium look like a fixed size,
+/// persistent `[u8]`, for users who don't want to deal with sectors.
+///
+/// Each access goes through whole sectors: [`get`](ByteStore::get) reads the
+/// sector the byte is in and [`set`](ByteStore::set) does a read-modify-write
+/// of it (see [`Storage::update_sector`]). This is convenient but slow for
+/// bulk accesses; use the sector level functions for those.
+///
+/// ```rust
+/// # use storage_traits::{ByteStore, SliceStorage};
+/// # use typenum::consts::U4;
+/// let mut bytes = [0u8; 12];
+/// let mut store = ByteStore::new(SliceStorage::<u8, U4>::new(&mut bytes));
+/// assert_eq!(store.len(), 12);
+///
+/// store.set(3, 0xAB).unwrap();
+/// store.set(9, 0xCD).unwrap();
+/// assert_eq!(store.get(3), Ok(0xAB));
+/// assert_eq!(store.get(9), Ok(0xCD));
+/// assert!(store.get(12).is_err());
+///
+/// let nonzero: Vec<_> = store.iter()
+///     .map(Result::unwrap)
+///     .enumerate()
+///     .filter(|(_, b)| *b != 0)
+///     .collect();
+/// assert_eq!(nonzero, vec![(3, 0xAB), (9, 0xCD)]);
+/// ```
+#[derive(Debug)]
+pub struct ByteStore<S: Storage<Word = u8>> {
+    inner: S,
+}
+
+impl<S: Storage<Word = u8>> ByteStore<S> {
+    /// Wraps `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Unwraps this facade, returning the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The number of bytes in the storage medium.
+    pub fn len(&self) -> usize {
+        self.inner.capacity_in_bytes()
+    }
+
+    /// Whether the storage medium has no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the byte at `index`.
+    pub fn get(&mut self, index: usize) -> Result<u8, ReadError<S::ReadErr>> {
+        if index >= self.len() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: index,
+                max_offset: self.len(),
+            });
+        }
+
+        let mut sector = GenericArray::default();
+        self.inner.read_sector(index / S::SECTOR_SIZE::to_usize(), &mut sector)?;
+
+        Ok(sector[index % S::SECTOR_SIZE::to_usize()])
+    }
+
+    /// Writes `byte` to `index`.
+    pub fn set(&mut self, index: usize, byte: u8) -> Result<(), UpdateError<S::ReadErr, S::WriteErr>> {
+        if index >= self.len() {
+            return Err(UpdateError::Read(ReadError::OutOfRange {
+                requested_offset: index,
+                max_offset: self.len(),
+            }));
+        }
+
+        self.inner.update_sector(index / S::SECTOR_SIZE::to_usize(), |sector| {
+            sector[index % S::SECTOR_SIZE::to_usize()] = byte;
+        })
+    }
+
+    /// Iterates over every byte in the storage medium, reading one sector at a
+    /// time.
+    pub fn iter(&mut self) -> Bytes<'_, S> {
+        Bytes {
+            inner: &mut self.inner,
+            sector: GenericArray::default(),
+            next: 0,
+        }
+    }
+}
+
+/// Iterator over the bytes in a [`ByteStore`]; see [`ByteStore::iter`].
+///
+/// Yields an error (and then stops) if a sector can't be read.
+#[derive(Debug)]
+pub struct Bytes<'a, S: Storage<Word = u8>> {
+    inner: &'a mut S,
+    sector: GenericArray<u8, S::SECTOR_SIZE>,
+    next: usize,
+}
+
+impl<S: Storage<Word = u8>> Iterator for Bytes<'_, S> {
+    type Item = Result<u8, ReadError<S::ReadErr>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.inner.capacity_in_bytes() {
+            return None;
+        }
+
+        let offset = self.next % S::SECTOR_SIZE::to_usize();
+        if offset == 0 {
+            if let Err(err) = self.inner.read_sector(self.next / S::SECTOR_SIZE::to_usize(), &mut self.sector) {
+                self.next = usize::MAX;
+                return Some(Err(err));
+            }
+        }
+
+        self.next += 1;
+        Some(Ok(self.sector[offset]))
+    }
+}
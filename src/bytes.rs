@@ -1,5 +1,9 @@
 //! Home of the [`AsBytes`](AsBytes) trait.
 
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::mem::size_of;
+
 // We really want const generics here so we can ask for `Self::NUM_BYTES` in
 // methods, but alas.
 /// Types that implement this can be constructed from a slice of [`u8`]s.
@@ -7,7 +11,7 @@
 /// [`u8`]: u8
 pub trait AsBytes: Sized {
     /// The number of bytes the implementing type needs to construct itself.
-    const NUM_BYTES: usize = core::mem::size_of::<Self>();
+    const NUM_BYTES: usize = size_of::<Self>();
 
     /// The array of bytes that the implementing type can be turned into.
     ///
@@ -47,7 +51,7 @@ pub trait AsBytes: Sized {
 macro_rules! impl_from_bytes {
     ($($ty:ty)*) => {$(
         impl AsBytes for $ty {
-            type To = [u8; core::mem::size_of::<Self>()];
+            type To = [u8; size_of::<Self>()];
 
             fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
                 if bytes.len() < Self::NUM_BYTES {
@@ -70,6 +74,211 @@ macro_rules! impl_from_bytes {
     )*};
 }
 
-impl_from_bytes! { u8 u16 u32 u64 u128 usize }
+impl_from_bytes! { u8 u16 u32 u64 u128 usize f32 f64 }
+
+/// Checks the invariants of a [`ValidatedWord`]'s underlying value.
+pub trait Validator<T> {
+    /// Whether `value` is valid.
+    fn validate(value: &T) -> bool;
+}
+
+/// A word whose value is checked by the [`Validator`] `V` whenever one is
+/// constructed, including when it's decoded with [`AsBytes::from`] (which
+/// returns `None` for invalid values, so they show up as decode errors like
+/// [`ReadError::InvalidData`](crate::errors::ReadError::InvalidData)).
+///
+/// This layers invariants (i.e. an enum-like `u8` that must be in `0..3`) on
+/// top of an existing [`AsBytes`] impl without having to write one by hand:
+///
+/// ```rust
+/// # use storage_traits::{AsBytes, ValidatedWord, Validator};
+/// #[derive(Debug)]
+/// struct UpTo3;
+///
+/// impl Validator<u8> for UpTo3 {
+///     fn validate(value: &u8) -> bool { *value < 3 }
+/// }
+///
+/// type State = ValidatedWord<u8, UpTo3>;
+///
+/// let (state, rest) = <State as AsBytes>::from(&[2, 7]).unwrap();
+/// assert_eq!(*state.get(), 2);
+/// assert_eq!(rest, &[7]);
+///
+/// assert!(<State as AsBytes>::from(&[3]).is_none());
+/// assert!(State::new(3).is_none());
+/// assert_eq!(State::new(1).unwrap().to(), [1]);
+/// ```
+pub struct ValidatedWord<T, V> {
+    value: T,
+    _v: PhantomData<fn() -> V>,
+}
+
+impl<T, V: Validator<T>> ValidatedWord<T, V> {
+    /// Wraps `value`, returning `None` if it isn't valid.
+    pub fn new(value: T) -> Option<Self> {
+        if V::validate(&value) {
+            Some(Self { value, _v: PhantomData })
+        } else {
+            None
+        }
+    }
+
+    /// Borrows the underlying value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwraps the underlying value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+// These are implemented by hand so that they don't require anything of `V`.
+impl<T: Debug, V> Debug for ValidatedWord<T, V> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.debug_tuple("ValidatedWord").field(&self.value).finish()
+    }
+}
+
+impl<T: Clone, V> Clone for ValidatedWord<T, V> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), _v: PhantomData }
+    }
+}
+
+impl<T: Copy, V> Copy for ValidatedWord<T, V> {}
+
+impl<T: PartialEq, V> PartialEq for ValidatedWord<T, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, V> Eq for ValidatedWord<T, V> {}
+
+impl<T: AsBytes, V: Validator<T>> AsBytes for ValidatedWord<T, V> {
+    const NUM_BYTES: usize = T::NUM_BYTES;
+
+    type To = T::To;
+
+    fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        let (value, rest) = T::from(bytes)?;
+        Some((Self::new(value)?, rest))
+    }
+
+    fn to(&self) -> Self::To {
+        self.value.to()
+    }
+}
+
+/// Compares words by their byte representations (see [`AsBytes::to`]) instead
+/// of with `PartialEq`; this is what's actually stored, and it works for words
+/// where the two disagree (i.e. NaN floats).
+pub(crate) fn bytes_eq<W: AsBytes>(a: &[W], b: &[W]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|(a, b)| a.to().as_ref() == b.to().as_ref())
+}
+
+
+
+/// Checks that `value` survives a trip through [`AsBytes::to`] and
+/// [`AsBytes::from`], panicking if it doesn't.
+///
+/// Hand-written [`AsBytes`] impls that don't roundtrip will silently corrupt
+/// data; this is meant to be used in tests to catch such impls early.
+///
+/// ```rust
+/// # use storage_traits::debug_assert_roundtrip;
+/// debug_assert_roundtrip(&0xDEAD_BEEFu32);
+/// ```
+///
+/// ```rust,should_panic
+/// # use storage_traits::{debug_assert_roundtrip, AsBytes};
+/// #[derive(Debug, PartialEq)]
+/// struct Broken(u16);
+///
+/// impl AsBytes for Broken {
+///     type To = [u8; 2];
+///
+///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
+///         let (val, rest) = <u16 as AsBytes>::from(bytes)?;
+///         Some((Broken(val.swap_bytes()), rest))
+///     }
+///
+///     fn to(&self) -> [u8; 2] { self.0.to_le_bytes() }
+/// }
+///
+/// debug_assert_roundtrip(&Broken(0x1234));
+/// ```
+pub fn debug_assert_roundtrip<T: AsBytes + PartialEq + Debug>(value: &T) {
+    let bytes = value.to();
+    let bytes = bytes.as_ref();
+
+    assert_eq!(
+        bytes.len(),
+        T::NUM_BYTES,
+        "`AsBytes::to` produced {} bytes but `NUM_BYTES` is {}",
+        bytes.len(),
+        T::NUM_BYTES,
+    );
 
+    match T::from(bytes) {
+        Some((ref decoded, rest)) => {
+            assert_eq!(decoded, value, "`AsBytes` roundtrip produced a different value");
+            assert!(rest.is_empty(), "`AsBytes::from` didn't consume all the bytes");
+        }
+        None => panic!("`AsBytes::from` failed to decode the output of `AsBytes::to`"),
+    }
+}
 
+using_std! {
+    /// Decodes words from the start of `bytes` until [`AsBytes::from`] fails,
+    /// returning the decoded words and the remaining bytes.
+    ///
+    /// For words that can hold any bit pattern this only stops when it runs out
+    /// of bytes, so the remainder is shorter than [`AsBytes::NUM_BYTES`]. Words
+    /// that reject some values (i.e. [`ValidatedWord`]) can stop early: the
+    /// first invalid word and everything after it are left in the remainder.
+    ///
+    /// ```rust
+    /// # use storage_traits::decode_words;
+    /// let (words, rest) = decode_words::<u32>(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0]);
+    ///
+    /// assert_eq!(words, vec![1, 2]);
+    /// assert_eq!(rest, &[3, 0]);
+    /// ```
+    pub fn decode_words<W: AsBytes>(mut bytes: &[u8]) -> (Vec<W>, &[u8]) {
+        // Zero-sized words would have us loop forever.
+        if W::NUM_BYTES == 0 {
+            return (Vec::new(), bytes);
+        }
+
+        let mut words = Vec::with_capacity(bytes.len() / W::NUM_BYTES);
+
+        while let Some((word, rest)) = W::from(bytes) {
+            words.push(word);
+            bytes = rest;
+        }
+
+        (words, bytes)
+    }
+
+    /// Encodes `words` into a flat buffer of bytes; the counterpart to
+    /// [`decode_words`].
+    ///
+    /// ```rust
+    /// # use storage_traits::encode_words;
+    /// assert_eq!(encode_words(&[1u16, 0x0302]), vec![1, 0, 2, 3]);
+    /// ```
+    pub fn encode_words<W: AsBytes>(words: &[W]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(words.len() * W::NUM_BYTES);
+
+        for word in words {
+            bytes.extend_from_slice(word.to().as_ref());
+        }
+
+        bytes
+    }
+}
@@ -0,0 +1,56 @@
+//! A small, table-less CRC-32 (IEEE 802.3) implementation.
+
+/// The reflected CRC-32 polynomial.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// An incremental CRC-32 (as used by zlib, Ethernet, etc.) calculator.
+///
+/// ```rust
+/// # use storage_traits::crc::{crc32, Crc32};
+/// let mut crc = Crc32::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+///
+/// assert_eq!(crc.finish(), 0xCBF4_3926);
+/// assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    /// Starts a new calculation.
+    pub fn new() -> Self {
+        Self { state: !0 }
+    }
+
+    /// Feeds `bytes` into the calculation.
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.state & 1).wrapping_neg();
+                self.state = (self.state >> 1) ^ (POLYNOMIAL & mask);
+            }
+        }
+    }
+
+    /// Returns the CRC of all the bytes fed in so far.
+    pub fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// Computes the CRC-32 of `bytes` in one go.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
@@ -0,0 +1,111 @@
+//! Home of the [`DynSectorStorage`](DynSectorStorage) backend.
+
+use super::errors::{ReadError, WriteError};
+
+use std::convert::Infallible;
+
+/// An in-memory, byte addressed storage medium whose sector size is only known
+/// at runtime (i.e. it comes from a device's reported geometry).
+///
+/// Because the sector size isn't a type level constant this can't implement
+/// [`Storage`](crate::Storage); instead it offers the same sector based
+/// operations on byte slices. This forgoes the guarantees the typed arrays give
+/// you: the lengths of buffers passed in are checked at runtime and mismatches
+/// are reported as errors.
+///
+/// ```rust
+/// # use storage_traits::{DynSectorStorage, errors::WriteError};
+/// let mut storage = DynSectorStorage::new(520, 4);
+/// assert_eq!(storage.capacity_in_bytes(), 2080);
+///
+/// let data: Vec<u8> = (0..520).map(|i| i as u8).collect();
+/// storage.write_sector(3, &data).unwrap();
+///
+/// let mut buf = vec![0; 520];
+/// storage.read_sector(3, &mut buf).unwrap();
+/// assert_eq!(buf, data);
+///
+/// assert_eq!(
+///     storage.write_sector(0, &data[..512]),
+///     Err(WriteError::InvalidNumberOfBytes { bytes_given: 512, bytes_in_a_sector: 520 }),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DynSectorStorage {
+    bytes: Vec<u8>,
+    sector_size: usize,
+}
+
+impl DynSectorStorage {
+    /// Creates a zeroed storage medium with `size_in_sectors` sectors of
+    /// `sector_size` bytes each.
+    pub fn new(sector_size: usize, size_in_sectors: usize) -> Self {
+        Self {
+            bytes: vec![0; sector_size.checked_mul(size_in_sectors).unwrap()],
+            sector_size,
+        }
+    }
+
+    /// In units of bytes.
+    pub fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    /// In units of sectors.
+    pub fn capacity(&self) -> usize {
+        self.bytes.len().checked_div(self.sector_size).unwrap_or(0)
+    }
+
+    /// In units of bytes.
+    pub fn capacity_in_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Reads in an entire sector; `buffer` must be exactly one sector long.
+    pub fn read_sector(
+        &self,
+        sector_idx: usize,
+        buffer: &mut [u8],
+    ) -> Result<(), ReadError<Infallible>> {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        if buffer.len() != self.sector_size {
+            return Err(ReadError::InvalidNumberOfBytes {
+                bytes_given: buffer.len(),
+                bytes_in_a_sector: self.sector_size,
+            });
+        }
+
+        buffer.copy_from_slice(&self.bytes[(sector_idx * self.sector_size)..][..self.sector_size]);
+        Ok(())
+    }
+
+    /// Writes out an entire sector; `bytes` must be exactly one sector long.
+    pub fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        bytes: &[u8],
+    ) -> Result<(), WriteError<Infallible>> {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        if bytes.len() != self.sector_size {
+            return Err(WriteError::InvalidNumberOfBytes {
+                bytes_given: bytes.len(),
+                bytes_in_a_sector: self.sector_size,
+            });
+        }
+
+        self.bytes[(sector_idx * self.sector_size)..][..self.sector_size].copy_from_slice(bytes);
+        Ok(())
+    }
+}
@@ -26,9 +26,32 @@ pub enum WriteError<E: Debug> {
 
     // TODO
     InvalidNumberOfBytes { bytes_given: usize, bytes_in_a_sector: usize },
-    // TODO
+    /// For writes of slices that aren't exactly a sector long (see
+    /// [`Storage::write_sector_with_words`](crate::Storage::write_sector_with_words)).
     InvalidNumberOfWords { words_given: usize, words_in_a_sector: usize },
 
+    /// For writes to a word that hasn't been erased since it was last written
+    /// to (see [`Flash`](crate::Flash)).
+    NotErased { offset: usize },
+
+    /// For writes to a storage medium that's write protected (see
+    /// [`HardwareProtect`](crate::HardwareProtect)). `offset` is in the units
+    /// of the rejected write (i.e. sectors for `write_sector`).
+    Protected { offset: usize },
+
+    /// For writes to a sector that has been marked bad (see
+    /// [`BadBlockAware`](crate::BadBlockAware)).
+    BadBlock { offset: usize },
+
+    /// For writes to a sector that has used up its write/erase budget (i.e.
+    /// with the `Endurance` adapter). `offset` is the index of the sector.
+    Worn { offset: usize },
+
+    /// For writes that need to read in the sector they're modifying first
+    /// (i.e. word writes that are turned into read-modify-writes) where that
+    /// read failed. `offset` is the index of the sector.
+    ReadFailed { offset: usize },
+
     Other(E),
 }
 
@@ -63,6 +86,24 @@ pub enum ReadError<E: Debug> {
     /// The `requested_offset` must be greater than the storage's capacity (i.e.
     /// out of range).
     OutOfRange { requested_offset: usize, max_offset: usize },
+    /// For reads into buffers that aren't the size of a sector (only for
+    /// storage mediums whose sector size isn't known at compile time; see
+    /// `DynSectorStorage`).
+    InvalidNumberOfBytes { bytes_given: usize, bytes_in_a_sector: usize },
+    /// For when the data that was read couldn't be decoded (i.e. by
+    /// [`AsBytes::from`](crate::AsBytes::from)).
+    InvalidData { offset: usize },
+    /// For when the data that was read is corrupted beyond repair (i.e. an
+    /// error correcting code detected more errors than it can correct).
+    Uncorrectable { offset: usize },
+    /// For reads of a sector that has been marked bad (see
+    /// [`BadBlockAware`](crate::BadBlockAware)).
+    BadBlock { offset: usize },
+    /// For multi-word reads whose offset or length isn't a multiple of the
+    /// storage medium's required alignment (see
+    /// [`WordReadable::read_alignment`](crate::WordReadable::read_alignment)).
+    /// Both `offset` and `alignment` are in words.
+    Unaligned { offset: usize, alignment: usize },
     /// Catch-all variant for implementation specific errors.
     Other(E),
 }
@@ -73,19 +114,311 @@ impl<E: Debug> From<E> for ReadError<E> {
     }
 }
 
+/// Converts the [`ReadError`] from the read half of a read-modify-write (i.e.
+/// a word write that's turned into a sector write) of the sector at
+/// `sector_idx` into the equivalent [`WriteError`].
+///
+/// `OutOfRange`, `InvalidNumberOfBytes`, and `BadBlock` map onto the
+/// `WriteError` variants of the same name. Everything else (failures that only
+/// make sense for reads, like `InvalidData`, `Uncorrectable`, `Unaligned`,
+/// and `Other` read errors, which can't be turned into write errors in
+/// general) becomes [`WriteError::ReadFailed`] for `sector_idx`; the read
+/// error's own offset isn't kept since it may not be a sector index. Use
+/// [`read_error_into_write_same`] to keep `Other` errors when the read and
+/// write error types are the same.
+///
+/// [`ReadError::Uninitialized`] maps to `None`: a sector that's never been
+/// written to has nothing in it worth preserving, so read-modify-write paths
+/// should treat it as fresh (i.e. start from a default sector) and carry on
+/// with the write rather than failing.
+///
+/// ```rust
+/// # use storage_traits::errors::{read_error_into_write, ReadError, WriteError};
+/// assert_eq!(
+///     read_error_into_write::<(), ()>(ReadError::OutOfRange { requested_offset: 9, max_offset: 8 }, 9),
+///     Some(WriteError::OutOfRange { requested_offset: 9, max_offset: 8 }),
+/// );
+/// assert_eq!(read_error_into_write::<(), ()>(ReadError::Uninitialized { offset: 4 }, 4), None);
+/// assert_eq!(
+///     read_error_into_write::<(), ()>(ReadError::InvalidNumberOfBytes { bytes_given: 3, bytes_in_a_sector: 4 }, 0),
+///     Some(WriteError::InvalidNumberOfBytes { bytes_given: 3, bytes_in_a_sector: 4 }),
+/// );
+/// assert_eq!(
+///     read_error_into_write::<(), ()>(ReadError::BadBlock { offset: 2 }, 2),
+///     Some(WriteError::BadBlock { offset: 2 }),
+/// );
+///
+/// // Word offsets aren't passed through:
+/// for err in [
+///     ReadError::InvalidData { offset: 21 },
+///     ReadError::Uncorrectable { offset: 21 },
+///     ReadError::Unaligned { offset: 21, alignment: 4 },
+///     ReadError::Other("oops"),
+/// ].iter() {
+///     assert_eq!(read_error_into_write::<_, ()>(*err, 5), Some(WriteError::ReadFailed { offset: 5 }));
+/// }
+/// ```
+pub fn read_error_into_write<R: Debug, W: Debug>(err: ReadError<R>, sector_idx: usize) -> Option<WriteError<W>> {
+    use ReadError::*;
+
+    Some(match err {
+        Uninitialized { .. } => return None,
+        OutOfRange { requested_offset, max_offset } =>
+            WriteError::OutOfRange { requested_offset, max_offset },
+        InvalidNumberOfBytes { bytes_given, bytes_in_a_sector } =>
+            WriteError::InvalidNumberOfBytes { bytes_given, bytes_in_a_sector },
+        BadBlock { offset } => WriteError::BadBlock { offset },
+        InvalidData { .. } | Uncorrectable { .. } | Unaligned { .. } | Other(_) =>
+            WriteError::ReadFailed { offset: sector_idx },
+    })
+}
+
+/// Like [`read_error_into_write`] but for storage mediums whose read and write
+/// errors share a type, so `Other` read errors can be kept as `Other` write
+/// errors instead of becoming [`WriteError::ReadFailed`].
+///
+/// ```rust
+/// # use storage_traits::errors::{read_error_into_write_same, ReadError, WriteError};
+/// assert_eq!(read_error_into_write_same(ReadError::Other("oops"), 5), Some(WriteError::Other("oops")));
+/// assert_eq!(
+///     read_error_into_write_same(ReadError::<()>::InvalidData { offset: 21 }, 5),
+///     Some(WriteError::ReadFailed { offset: 5 }),
+/// );
+/// assert_eq!(read_error_into_write_same(ReadError::<()>::Uninitialized { offset: 4 }, 1), None);
+/// ```
+pub fn read_error_into_write_same<E: Debug>(err: ReadError<E>, sector_idx: usize) -> Option<WriteError<E>> {
+    match err {
+        ReadError::Other(other) => Some(WriteError::Other(other)),
+        err => read_error_into_write(err, sector_idx),
+    }
+}
+
+// The `requested_offset` to report for the `len` words (or sectors) starting at
+// `start` that don't fit: the last one in the range, or `start` itself if the
+// range is empty.
+pub(crate) fn last_offset(start: usize, len: usize) -> usize {
+    match len.checked_sub(1) {
+        Some(last) => start.saturating_add(last),
+        None => start,
+    }
+}
+
+/// Errors that can occur when erasing a storage medium (see
+/// [`Eraseable`](crate::Eraseable)).
+///
+/// [`WriteError`]s convert into
+/// [`ErrorInIndividualErase`](EraseError::ErrorInIndividualErase) so erase
+/// loops built on sector writes can use `?` directly:
+///
+/// ```rust
+/// # use storage_traits::errors::{EraseError, WriteError};
+/// fn write_sector(idx: usize) -> Result<(), WriteError<()>> {
+///     if idx < 3 { Ok(()) } else { Err(WriteError::OutOfRange { requested_offset: idx, max_offset: 3 }) }
+/// }
+///
+/// fn erase(sectors: usize) -> Result<(), EraseError<(), &'static str>> {
+///     for idx in 0..sectors {
+///         write_sector(idx)?;
+///     }
+///     Ok(())
+/// }
+///
+/// assert_eq!(erase(3), Ok(()));
+/// assert_eq!(
+///     erase(4),
+///     Err(EraseError::ErrorInIndividualErase(WriteError::OutOfRange { requested_offset: 3, max_offset: 3 })),
+/// );
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum EraseError<W: Debug, E: Debug> {
     ErrorInIndividualErase(WriteError<W>),
+    /// For strided erases (see
+    /// [`Eraseable::erase_strided`](crate::Eraseable::erase_strided)) with a
+    /// stride of zero.
+    ZeroStride,
     Other(E),
 }
 
-impl<W: Debug, E: Debug> From<E> for EraseError<W, E> {
-    fn from(other: E) -> Self {
-        EraseError::Other(other)
+// This can't also have a blanket `From<E>` impl (like `WriteError` and
+// `ReadError` do) since the two would overlap when `E` is a `WriteError<W>`.
+impl<W: Debug, E: Debug> From<WriteError<W>> for EraseError<W, E> {
+    fn from(err: WriteError<W>) -> Self {
+        EraseError::ErrorInIndividualErase(err)
+    }
+}
+
+/// A [`ReadError`] from a multi-word read that failed partway through, along
+/// with how many words were successfully read before the failure (see
+/// [`WordReadable::read_words_partial`](crate::WordReadable::read_words_partial)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PartialReadError<E: Debug> {
+    /// The number of words at the start of the buffer that hold valid data.
+    pub words_read: usize,
+    /// The error that stopped the read.
+    pub error: ReadError<E>,
+}
+
+impl<E: Debug> From<PartialReadError<E>> for ReadError<E> {
+    fn from(err: PartialReadError<E>) -> Self {
+        err.error
+    }
+}
+
+/// Errors that can occur when doing a read-modify-write of a sector (i.e. with
+/// [`Storage::update_sector`](crate::Storage::update_sector)).
+///
+/// Both [`ReadError`]s and [`WriteError`]s convert into this (into the variant
+/// for their phase) so `?` can be used on either in functions that return
+/// this.
+///
+/// ```rust
+/// # use storage_traits::errors::{ReadError, UpdateError, WriteError};
+/// fn read() -> Result<u8, ReadError<()>> { Err(ReadError::Other(())) }
+/// fn write(_: u8) -> Result<(), WriteError<()>> { Err(WriteError::Other(())) }
+///
+/// fn read_modify_write(skip_read: bool) -> Result<(), UpdateError<(), ()>> {
+///     let val = if skip_read { 0 } else { read()? };
+///     write(val + 1)?;
+///     Ok(())
+/// }
+///
+/// assert_eq!(read_modify_write(false), Err(UpdateError::Read(ReadError::Other(()))));
+/// assert_eq!(read_modify_write(true), Err(UpdateError::Write(WriteError::Other(()))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateError<R: Debug, W: Debug> {
+    /// The error happened while reading the sector in.
+    Read(ReadError<R>),
+    /// The error happened while writing the sector back out.
+    Write(WriteError<W>),
+}
+
+impl<R: Debug, W: Debug> From<ReadError<R>> for UpdateError<R, W> {
+    fn from(err: ReadError<R>) -> Self {
+        UpdateError::Read(err)
+    }
+}
+
+impl<R: Debug, W: Debug> From<WriteError<W>> for UpdateError<R, W> {
+    fn from(err: WriteError<W>) -> Self {
+        UpdateError::Write(err)
+    }
+}
+
+/// Errors that can occur when running
+/// [`StorageExt::self_test`](crate::StorageExt::self_test).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelfTestError<R: Debug, W: Debug> {
+    /// Reading a sector (either the original contents or the test pattern)
+    /// failed.
+    Read(ReadError<R>),
+    /// Writing a sector (either the test pattern or the original contents)
+    /// failed.
+    Write(WriteError<W>),
+    /// The test pattern that was written to the sector at `sector_idx` didn't
+    /// read back correctly.
+    Mismatch { sector_idx: usize },
+}
+
+/// Errors that can occur when reading through a
+/// [`SplitWord`](crate::SplitWord)'s byte addressed view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SplitReadError<M: Debug, D: Debug> {
+    /// The byte offset is past the end of both regions. Offsets are in bytes.
+    OutOfRange { requested_offset: usize, max_offset: usize },
+    /// Reading from the metadata region failed.
+    Meta(ReadError<M>),
+    /// Reading from the data region failed.
+    Data(ReadError<D>),
+}
+
+#[cfg(feature = "crc")]
+using_std! {
+    /// Errors that can occur when verifying a storage medium against a
+    /// manifest (see [`StorageExt::verify_manifest`](crate::StorageExt::verify_manifest)).
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum VerifyError<E: Debug> {
+        /// Reading the storage medium failed; verification stops at the first
+        /// read error.
+        Read(ReadError<E>),
+        /// The CRCs for some of the ranges in the manifest didn't match; this
+        /// holds the indices (into the manifest) of every range that failed.
+        Mismatch { failed: Vec<usize> },
+    }
+
+    impl<E: Debug> From<ReadError<E>> for VerifyError<E> {
+        fn from(err: ReadError<E>) -> Self {
+            VerifyError::Read(err)
+        }
+    }
+}
+
+using_std! {
+    /// Errors that can occur when streaming data between a storage medium and
+    /// an I/O source or sink (i.e. with
+    /// [`StorageExt::write_from_reader`](crate::StorageExt::write_from_reader)).
+    ///
+    /// `E` is the storage medium's error type for the direction of the
+    /// transfer (a [`WriteError`] or a [`ReadError`]).
+    #[derive(Debug)]
+    pub enum StreamError<E: Debug> {
+        /// The source or sink failed.
+        Io(std::io::Error),
+        /// The storage medium failed.
+        Storage(E),
+    }
+
+    impl<E: Debug> From<std::io::Error> for StreamError<E> {
+        fn from(err: std::io::Error) -> Self {
+            StreamError::Io(err)
+        }
+    }
+}
+
+using_std! {
+    /// Errors that can occur when replaying a trace recorded by a
+    /// [`Recorder`](crate::adapters::Recorder) (see
+    /// [`replay`](crate::adapters::replay)).
+    #[derive(Debug)]
+    pub enum ReplayError<R: Debug, W: Debug> {
+        /// Reading the trace failed (this includes traces that end partway
+        /// through an operation).
+        Io(std::io::Error),
+        /// The operation at index `op` (counting from 0) isn't a valid
+        /// operation for the target.
+        Malformed { op: usize },
+        /// Replaying a read on the target failed.
+        Read(ReadError<R>),
+        /// Replaying a write (or an erase) on the target failed.
+        Write(WriteError<W>),
+    }
+
+    impl<R: Debug, W: Debug> From<std::io::Error> for ReplayError<R, W> {
+        fn from(err: std::io::Error) -> Self {
+            ReplayError::Io(err)
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+using_std! {
+    /// The implementation specific write errors of a
+    /// [`Compressed`](crate::adapters::Compressed) storage medium.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum CompressError<E: Debug> {
+        /// The underlying storage medium failed.
+        Inner(E),
+        /// The compressed sector didn't fit in the underlying storage, even
+        /// with every other live sector compacted. Both sizes are in bytes.
+        Full { needed: usize, available: usize },
     }
 }
 
+// The `Display` and `Error` impls below are behind the (default) `std-error`
+// feature; users who'd rather provide their own presentation layer can turn it
+// off (i.e. and use a newtype).
+#[cfg(feature = "std-error")]
 using_std! {
     use std::fmt::{self, Display};
 
@@ -107,5 +440,6 @@ using_std! {
 
     err!(WriteError);
     err!(ReadError);
+    err!(StreamError);
     // err!(EraseError); // TODO: fix
 }
@@ -1,7 +1,12 @@
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
 
-use super::{Storage, errors::EraseError};
+use super::{AsBytes, Storage, errors::{EraseError, ReadError, WriteError}};
 
-use core::fmt::Debug;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+
+use generic_array::{ArrayLength, GenericArray};
+use typenum::marker_traits::Unsigned;
 
 // TODO!
 
@@ -9,20 +14,170 @@ use core::fmt::Debug;
 /// EEPROM, for example).
 pub trait WordWritable: Storage {
     // #[inline] // <-- just documentation, doesn't actually do anything
-    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), ()>;
+    fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>>;
+
+    /// Writes out some chunk of data, starting at `word_offset`. As with
+    /// [`WordReadable::read_words`](crate::WordReadable::read_words) there's no
+    /// guarantee that the chunk is aligned to a sector or smaller than a
+    /// sector.
+    ///
+    /// `offset + words.len()` must not exceed `self.capacity_in_words()` for
+    /// this to succeed; this is checked before anything is written. Zero-length
+    /// writes always succeed (and do nothing) for any `offset` in
+    /// [0, `self.capacity_in_words()`], including one-past-the-end.
+    ///
+    /// This function has a naïve default implementation that calls
+    /// [`write_word`](WordWritable::write_word) for each word. Since it's only
+    /// available on storage mediums that can write words, it never has to fall
+    /// back to a read-modify-write of the sectors it touches: only the affected
+    /// words are written and nothing is read. (Adapters like
+    /// [`Coalescing`](crate::adapters::Coalescing) provide this trait for
+    /// storage mediums that _can't_ write words, with read-modify-writes.)
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, WordWritable, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// // An EEPROM-like storage medium that logs every access:
+    /// #[derive(Debug, Default)]
+    /// struct Logged { words: [u8; 8], log: Vec<&'static str> }
+    /// # impl Storage for Logged {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 2 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         self.log.push("read_sector"); buf.copy_from_slice(&self.words[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.log.push("write_sector"); self.words[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl WordWritable for Logged {
+    /// #     fn write_word(&mut self, addr: usize, word: u8) -> Result<(), WriteError<()>> {
+    /// #         self.log.push("write_word"); self.words[addr] = word; Ok(())
+    /// #     }
+    /// # }
+    ///
+    /// let mut storage = Logged { words: [9; 8], log: Vec::new() };
+    /// storage.write_words(3, &[1, 2]).unwrap();
+    ///
+    /// assert_eq!(storage.log, ["write_word", "write_word"]);
+    /// assert_eq!(storage.words, [9, 9, 9, 1, 2, 9, 9, 9]);
+    /// ```
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, WordReadable, WordWritable};
+    /// # use storage_traits::errors::{ReadError, WriteError};
+    /// # use typenum::consts::U4;
+    /// let mut backing = [0u8; 8];
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut backing);
+    ///
+    /// // Zero-length operations are fine anywhere up to one-past-the-end:
+    /// for offset in [0, 5, 8] {
+    ///     storage.write_words(offset, &[]).unwrap();
+    ///     storage.read_words(offset, &mut []).unwrap();
+    /// }
+    ///
+    /// // ...but not past that:
+    /// assert!(matches!(storage.write_words(9, &[]), Err(WriteError::OutOfRange { .. })));
+    /// assert!(matches!(storage.read_words(9, &mut []), Err(ReadError::OutOfRange { .. })));
+    ///
+    /// storage.write_words(3, &[1, 2, 3]).unwrap();
+    /// assert!(matches!(storage.write_words(6, &[1, 2, 3]), Err(WriteError::OutOfRange { .. })));
+    ///
+    /// let mut buf = [0; 4];
+    /// storage.read_words(2, &mut buf).unwrap();
+    /// assert_eq!(buf, [0, 1, 2, 3]);
+    /// ```
+    fn write_words(
+        &mut self,
+        word_offset: usize,
+        words: &[Self::Word],
+    ) -> Result<(), WriteError<Self::WriteErr>>
+    where
+        Self::Word: Clone,
+    {
+        match word_offset.checked_add(words.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, words.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+
+        for (idx, word) in words.iter().enumerate() {
+            self.write_word(word_offset + idx, word.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Forces any word writes that are pending (i.e. buffered) out to the
+    /// storage medium.
+    ///
+    /// Implementations that don't buffer word writes don't need to override
+    /// this; the default does nothing.
+    fn flush_words(&mut self) -> Result<(), WriteError<Self::WriteErr>> {
+        Ok(())
+    }
 }
 
-// TODO!
+/// Proof that a sector has been erased (and a record of which words in it have
+/// been written to since).
+///
+/// These can only be obtained through [`Flash::erase_sector`]; since there's no
+/// other way to construct one, having one of these means that the sector it's
+/// for really was erased.
+///
+/// [`Flash::erase_sector`]: Flash::erase_sector
+pub struct ErasedPageToken<F: Flash + ?Sized>
+where
+    F::SECTOR_SIZE: ArrayLength<bool>,
+{
+    /// The index of the sector that this token is for.
+    sector_idx: usize,
+    /// A record of the words in this sector that have been written to.
+    record: GenericArray<bool, F::SECTOR_SIZE>,
+    _f: PhantomData<F>,
+}
 
-// pub struct ErasedPageToken<F: Flash + ?Sized> {
-//     /// The index of the sector that this token is for.
-//     sector_idx: usize,
-//     /// A record of the sectors in this page.
-//     record: [u8; <F as Storage>::SECTOR_SIZE],
-//     _f: core::marker::PhantomData<F>,
-// }
+impl<F: Flash + ?Sized> ErasedPageToken<F>
+where
+    F::SECTOR_SIZE: ArrayLength<bool>,
+{
+    /// The index of the sector that this token is for.
+    pub fn sector_idx(&self) -> usize {
+        self.sector_idx
+    }
 
-// TODO!
+    /// Whether the word at `word_offset` (in units of words, relative to the
+    /// start of the storage medium) has been written to since the sector was
+    /// erased.
+    ///
+    /// Returns `None` if `word_offset` isn't in the sector this token is for.
+    pub fn is_written(&self, word_offset: usize) -> Option<bool> {
+        self.sector_offset(word_offset).map(|idx| self.record[idx])
+    }
+
+    fn sector_offset(&self, word_offset: usize) -> Option<usize> {
+        let start = self.sector_idx * F::SECTOR_SIZE::to_usize();
+
+        word_offset
+            .checked_sub(start)
+            .filter(|idx| *idx < F::SECTOR_SIZE::to_usize())
+    }
+}
+
+impl<F: Flash + ?Sized> Debug for ErasedPageToken<F>
+where
+    F::SECTOR_SIZE: ArrayLength<bool>,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ErasedPageToken")
+            .field("sector_idx", &self.sector_idx)
+            .field("record", &self.record.as_slice())
+            .finish()
+    }
+}
 
 /// An extension to the `Storage` trait that allows for complicated access
 /// schemes that wish to write to erased pages gradually. Tracking of these
@@ -32,16 +187,149 @@ pub trait WordWritable: Storage {
 /// have yet to be cleared or have already been written to since being cleared;
 /// you are prevented from doing this).
 ///
+/// Word writes require an [`ErasedPageToken`] for the sector being written to
+/// which can only be had by erasing that sector with
+/// [`erase_sector`](Flash::erase_sector). The token then keeps track of which
+/// words in the sector have been written to; attempts to write to a word twice
+/// return a [`WriteError::NotErased`] error.
+///
 /// This does, however, assume that there are not multiple instances of the
-/// type this is implemented on *with overlapping address ranges*.
+/// type this is implemented on *with overlapping address ranges*. It also
+/// assumes that the sector isn't modified through other means (i.e.
+/// [`Storage::write_sector`]) while a token for it is held.
+///
+/// Implementors provide [`raw_erase_sector`](Flash::raw_erase_sector) and
+/// [`raw_write_word`](Flash::raw_write_word); users should use the provided
+/// [`erase_sector`](Flash::erase_sector) and [`write_word`](Flash::write_word)
+/// functions which do the bookkeeping.
+///
+/// ```rust
+/// # use storage_traits::{errors::WriteError, Flash, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # #[derive(Debug, Default)]
+/// # struct Mock([u8; 8]);
+/// # impl Storage for Mock {
+/// #     type Word = u8;
+/// #     type SECTOR_SIZE = U4;
+/// #     type ReadErr = ();
+/// #     type WriteErr = ();
+/// #     fn capacity(&self) -> usize { 2 }
+/// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U4>) -> Result<(), storage_traits::errors::ReadError<()>> { unimplemented!() }
+/// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> { unimplemented!() }
+/// # }
+/// impl Flash for Mock {
+///     unsafe fn raw_erase_sector(&mut self, sector_idx: usize) -> Result<(), WriteError<()>> {
+///         for w in self.0[(sector_idx * 4)..][..4].iter_mut() { *w = 0xFF; }
+///         Ok(())
+///     }
+///
+///     unsafe fn raw_write_word(&mut self, word_offset: usize, word: u8) -> Result<(), WriteError<()>> {
+///         self.0[word_offset] = word;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut flash = Mock::default();
+/// let mut token = flash.erase_sector(1).unwrap();
+///
+/// flash.write_word(&mut token, 5, 0xAB).unwrap();
+/// assert_eq!(flash.0, [0, 0, 0, 0, 0xFF, 0xAB, 0xFF, 0xFF]);
+///
+/// // Words can't be written to twice without another erase:
+/// assert_eq!(flash.write_word(&mut token, 5, 0x12), Err(WriteError::NotErased { offset: 5 }));
+///
+/// // And tokens are only good for their own sector:
+/// assert!(flash.write_word(&mut token, 2, 0x12).is_err());
+/// ```
+///
+/// There's no way to write a word without a token:
+/// ```compile_fail
+/// # use storage_traits::ErasedPageToken;
+/// # fn make<F: storage_traits::Flash>() -> ErasedPageToken<F>
+/// # where F::SECTOR_SIZE: generic_array::ArrayLength<bool> {
+/// ErasedPageToken { sector_idx: 0, record: Default::default(), _f: core::marker::PhantomData }
+/// # }
+/// ```
 pub trait Flash: Storage {
-    // // Errors if `sector_idx` is not in [0, `self.capacity()`).
-    // fn erase_sector(&mut self, sector_idx: usize) -> Result<ErasedPageToken<Self>, ()>;
+    /// Erases the sector at `sector_idx`.
+    ///
+    /// # Safety
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`). Implementors can rely
+    /// on this; use [`erase_sector`](Flash::erase_sector), which checks,
+    /// instead of calling this directly.
+    #[allow(unsafe_code)]
+    unsafe fn raw_erase_sector(&mut self, sector_idx: usize) -> Result<(), WriteError<Self::WriteErr>>;
+
+    /// Writes a single word to a location that's been erased.
+    ///
+    /// # Safety
+    ///
+    /// `word_offset` must be in range and the word there must have been erased
+    /// and not written to since. Implementors can rely on this; use
+    /// [`write_word`](Flash::write_word), which checks (with an
+    /// [`ErasedPageToken`]), instead of calling this directly.
+    #[allow(unsafe_code)]
+    unsafe fn raw_write_word(&mut self, word_offset: usize, word: Self::Word) -> Result<(), WriteError<Self::WriteErr>>;
+
+    /// Erases a sector and returns a token that can be used to write to the
+    /// words in it.
+    ///
+    /// Errors if `sector_idx` is not in [0, `self.capacity()`).
+    #[allow(unsafe_code)]
+    fn erase_sector(&mut self, sector_idx: usize) -> Result<ErasedPageToken<Self>, WriteError<Self::WriteErr>>
+    where
+        Self::SECTOR_SIZE: ArrayLength<bool>,
+    {
+        if sector_idx >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
 
-    // /// `addr` must be [0, `self.capacity_in_words()`) for this to succeed.
-    // #[allow(unsafe_code)]
-    // unsafe fn write_word(&mut self, addr: usize, word: Self::Word) -> Result<(), ()>;
+        // Safety: `sector_idx` was checked above.
+        unsafe { self.raw_erase_sector(sector_idx)? };
 
+        Ok(ErasedPageToken {
+            sector_idx,
+            record: GenericArray::default(),
+            _f: PhantomData,
+        })
+    }
+
+    /// Writes a word to the sector `token` is for.
+    ///
+    /// `word_offset` (in units of words, relative to the start of the storage
+    /// medium) must be within the sector `token` is for and must not have been
+    /// written to since the sector was erased.
+    #[allow(unsafe_code)]
+    fn write_word(
+        &mut self,
+        token: &mut ErasedPageToken<Self>,
+        word_offset: usize,
+        word: Self::Word,
+    ) -> Result<(), WriteError<Self::WriteErr>>
+    where
+        Self::SECTOR_SIZE: ArrayLength<bool>,
+    {
+        let idx = token.sector_offset(word_offset).ok_or(WriteError::OutOfRange {
+            requested_offset: word_offset,
+            max_offset: (token.sector_idx + 1) * Self::SECTOR_SIZE::to_usize(),
+        })?;
+
+        if token.record[idx] {
+            return Err(WriteError::NotErased { offset: word_offset });
+        }
+
+        // Safety: `token` proves that the sector was erased and its record
+        // says this word hasn't been written to since.
+        unsafe { self.raw_write_word(word_offset, word)? };
+        token.record[idx] = true;
+
+        Ok(())
+    }
 }
 
 
@@ -50,7 +338,662 @@ pub trait Flash: Storage {
 pub trait Eraseable: Storage {
     type EraseErr: Debug;
 
+    /// The value every word has after an erase (i.e. `0xFF` for NOR flash).
+    const ERASED_WORD: Self::Word;
+
     /// Erases the entirety of the storage medium/partition/section that this
     /// instance corresponds to.
     fn erase(&mut self) -> Result<(), EraseError<<Self as Storage>::WriteErr, Self::EraseErr>>;
+
+    /// Checks that every word in the storage medium is
+    /// [`ERASED_WORD`](Eraseable::ERASED_WORD) (i.e. that an
+    /// [`erase`](Eraseable::erase) actually worked).
+    ///
+    /// See [`first_unerased_sector`](Eraseable::first_unerased_sector) for
+    /// which sector failed.
+    fn verify_erased(&mut self) -> Result<bool, ReadError<Self::ReadErr>>
+    where
+        Self::Word: Default,
+    {
+        Ok(self.first_unerased_sector()?.is_none())
+    }
+
+    /// Returns the index of the first sector that has a word that isn't
+    /// [`ERASED_WORD`](Eraseable::ERASED_WORD), if any.
+    ///
+    /// The default implementation reads every sector and compares words byte
+    /// for byte (see [`AsBytes::to`]); storage mediums that
+    /// can check this more cheaply (i.e. with a status register) should
+    /// override this.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Storage, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Nor([u8; 16]);
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 4 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl Eraseable for Nor {
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 16]; Ok(()) }
+    /// # }
+    /// let mut nor = Nor([0; 16]);
+    /// assert_eq!(nor.verify_erased(), Ok(false));
+    ///
+    /// nor.erase().unwrap();
+    /// assert_eq!(nor.verify_erased(), Ok(true));
+    ///
+    /// nor.write_sector(2, &GenericArray::from([0xFF, 0xFF, 0xFE, 0xFF])).unwrap();
+    /// assert_eq!(nor.verify_erased(), Ok(false));
+    /// assert_eq!(nor.first_unerased_sector(), Ok(Some(2)));
+    /// ```
+    fn first_unerased_sector(&mut self) -> Result<Option<usize>, ReadError<Self::ReadErr>>
+    where
+        Self::Word: Default,
+    {
+        let mut sector = GenericArray::default();
+
+        for sector_idx in 0..self.capacity() {
+            self.read_sector(sector_idx, &mut sector)?;
+
+            if !is_erased::<Self>(&sector) {
+                return Ok(Some(sector_idx));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Erases `count` sectors, starting at `start` and then every `stride`
+    /// sectors after that (i.e. for wear leveling stress tests), using
+    /// [`Flash::erase_sector`].
+    ///
+    /// The whole pattern is bounds checked before anything is erased; a
+    /// `stride` of zero is rejected with [`EraseError::ZeroStride`].
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Flash, Storage, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Nor([u8; 40]);
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 10 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl Flash for Nor {
+    /// #     unsafe fn raw_erase_sector(&mut self, idx: usize) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx * 4..][..4].copy_from_slice(&[0xFF; 4]); Ok(())
+    /// #     }
+    /// #     unsafe fn raw_write_word(&mut self, offset: usize, word: u8) -> Result<(), WriteError<()>> {
+    /// #         self.0[offset] = word; Ok(())
+    /// #     }
+    /// # }
+    /// # impl Eraseable for Nor {
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 40]; Ok(()) }
+    /// # }
+    /// let mut nor = Nor([0; 40]);
+    ///
+    /// // Every third sector:
+    /// nor.erase_strided(0, 3, 4).unwrap();
+    /// let erased: Vec<_> = (0..10).filter(|idx| nor.0[idx * 4] == 0xFF).collect();
+    /// assert_eq!(erased, [0, 3, 6, 9]);
+    ///
+    /// // Patterns that run off the end are rejected up front:
+    /// assert_eq!(
+    ///     nor.erase_strided(1, 3, 4),
+    ///     Err(EraseError::ErrorInIndividualErase(WriteError::OutOfRange { requested_offset: 10, max_offset: 10 })),
+    /// );
+    /// assert_eq!(nor.0[4], 0);
+    ///
+    /// assert_eq!(nor.erase_strided(0, 0, 1), Err(EraseError::ZeroStride));
+    /// ```
+    fn erase_strided(
+        &mut self,
+        start: usize,
+        stride: usize,
+        count: usize,
+    ) -> Result<(), EraseError<<Self as Storage>::WriteErr, Self::EraseErr>>
+    where
+        Self: Flash,
+        Self::SECTOR_SIZE: ArrayLength<bool>,
+    {
+        if stride == 0 {
+            return Err(EraseError::ZeroStride);
+        }
+
+        // One past the last sector that's erased:
+        let end = match count.checked_sub(1) {
+            Some(last) => last.checked_mul(stride)
+                .and_then(|span| span.checked_add(start))
+                .and_then(|last| last.checked_add(1)),
+            None => Some(start),
+        };
+
+        match end {
+            Some(end) if end <= self.capacity() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: end.unwrap_or(usize::MAX).saturating_sub(1),
+                max_offset: self.capacity(),
+            }.into()),
+        }
+
+        for idx in 0..count {
+            let _ = self.erase_sector(start + idx * stride)?;
+        }
+
+        Ok(())
+    }
+
+    /// An upper bound on how long erasing a single sector takes (i.e. the
+    /// datasheet's maximum sector erase time), for schedulers that budget time
+    /// for erases.
+    ///
+    /// The default is `None` (unknown).
+    fn max_erase_duration(&self) -> Option<core::time::Duration> {
+        None
+    }
+
+    /// An upper bound on how long erasing `sectors` sectors (i.e. with
+    /// [`erase_strided`](Eraseable::erase_strided)) takes, based on
+    /// [`max_erase_duration`](Eraseable::max_erase_duration).
+    ///
+    /// Returns `None` if the per-sector bound is unknown or if the total
+    /// overflows.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Storage, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # use std::time::Duration;
+    /// # #[derive(Debug)]
+    /// # struct Nor;
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 64 }
+    /// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> { unimplemented!() }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> { unimplemented!() }
+    /// # }
+    /// impl Eraseable for Nor {
+    ///     // ...
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { unimplemented!() }
+    ///     fn max_erase_duration(&self) -> Option<Duration> {
+    ///         Some(Duration::from_millis(5))
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(Nor.max_erase_duration_for(12), Some(Duration::from_millis(60)));
+    /// assert_eq!(Nor.max_erase_duration_for(usize::MAX), None);
+    /// ```
+    fn max_erase_duration_for(&self, sectors: usize) -> Option<core::time::Duration> {
+        use core::convert::TryInto;
+
+        let sectors: u32 = sectors.try_into().ok()?;
+
+        self.max_erase_duration()?.checked_mul(sectors)
+    }
+}
+
+
+/// Whether every word in `sector` is [`Eraseable::ERASED_WORD`] (compared byte
+/// for byte).
+pub(crate) fn is_erased<S: Eraseable + ?Sized>(sector: &[S::Word]) -> bool {
+    let erased = S::ERASED_WORD.to();
+
+    sector.iter().all(|word| word.to().as_ref() == erased.as_ref())
+}
+
+/// The [`PhysicalMapping::physical_sector`] of backends that aren't layered on
+/// anything: every in-range sector is its own physical sector.
+pub(crate) fn identity_mapping<S: Storage + ?Sized>(storage: &S, logical: usize) -> Option<usize> {
+    if logical < storage.capacity() { Some(logical) } else { None }
+}
+
+
+/// What storage mediums that keep track of which sectors have been written to
+/// (see [`InitTracking`]) should do when asked to read a sector that hasn't
+/// been written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UninitPolicy<W> {
+    /// Return [`ReadError::Uninitialized`]. This is the default.
+    #[default]
+    Error,
+    /// Return words whose bytes are all zeros.
+    Zero,
+    /// Return the given word (i.e. the value the medium has when erased).
+    EraseValue(W),
+}
+
+/// For storage mediums that keep track of which sectors have been written to
+/// (i.e. sparse or overlay backed storage).
+pub trait InitTracking: Storage {
+    /// Returns the index of the first sector at or after `sector_idx` that has
+    /// been written to, if there is one.
+    fn next_initialized_sector(&self, sector_idx: usize) -> Option<usize>;
+
+    /// Whether the sector at `sector_idx` has been written to.
+    fn is_initialized(&self, sector_idx: usize) -> bool {
+        self.next_initialized_sector(sector_idx) == Some(sector_idx)
+    }
+
+    /// Iterates over the sectors that have been written to (and their
+    /// contents), skipping those that haven't.
+    fn written_sectors(&mut self) -> WrittenSectors<'_, Self>
+    where
+        Self::Word: Default,
+    {
+        WrittenSectors { storage: self, next: 0 }
+    }
+
+    /// Counts the sectors that have (and haven't) been written to, without
+    /// reading any of them; the cheap counterpart to
+    /// [`StorageExt::usage`](crate::StorageExt::usage).
+    ///
+    /// Sectors that haven't been written to count as erased. Like
+    /// [`written_sectors`](InitTracking::written_sectors), this stops at the
+    /// capacity; sectors past it that are still tracked (i.e. after the
+    /// storage medium shrank) aren't counted:
+    ///
+    /// ```rust
+    /// # use storage_traits::{InitTracking, Storage, Usage, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Shrunk;
+    /// # impl Storage for Shrunk {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 3 }
+    /// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> { Ok(()) }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> { Ok(()) }
+    /// # }
+    /// // Sectors 1, 5 and 8 have been written to, but only 3 sectors remain:
+    /// impl InitTracking for Shrunk {
+    ///     fn next_initialized_sector(&self, sector_idx: usize) -> Option<usize> {
+    ///         [1, 5, 8].iter().copied().find(|idx| *idx >= sector_idx)
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(
+    ///     Shrunk.tracked_usage(),
+    ///     Usage { total_sectors: 3, erased_sectors: 2, written_sectors: 1 },
+    /// );
+    /// ```
+    fn tracked_usage(&self) -> crate::Usage {
+        let total_sectors = self.capacity();
+        let mut written_sectors = 0;
+
+        let mut next = self.next_initialized_sector(0).filter(|idx| *idx < total_sectors);
+        while let Some(sector_idx) = next {
+            written_sectors += 1;
+            next = self
+                .next_initialized_sector(sector_idx + 1)
+                .filter(|idx| *idx < total_sectors);
+        }
+
+        crate::Usage {
+            total_sectors,
+            erased_sectors: total_sectors - written_sectors,
+            written_sectors,
+        }
+    }
+}
+
+/// Iterator over the sectors in a storage medium that have been written to; see
+/// [`InitTracking::written_sectors`].
+#[derive(Debug)]
+pub struct WrittenSectors<'a, S: InitTracking + ?Sized> {
+    storage: &'a mut S,
+    next: usize,
+}
+
+impl<'a, S: InitTracking + ?Sized> Iterator for WrittenSectors<'a, S>
+where
+    S::Word: Default,
+{
+    type Item = Result<(usize, GenericArray<S::Word, S::SECTOR_SIZE>), ReadError<S::ReadErr>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sector_idx = self
+            .storage
+            .next_initialized_sector(self.next)
+            .filter(|idx| *idx < self.storage.capacity())?;
+        self.next = sector_idx + 1;
+
+        let mut sector = GenericArray::default();
+        Some(self.storage.read_sector(sector_idx, &mut sector).map(|()| (sector_idx, sector)))
+    }
+}
+
+
+/// For storage mediums that are directly addressable (i.e. backed by memory)
+/// and can hand out references to their sectors instead of copying them.
+///
+/// Storage mediums that have to go through some kind of I/O (i.e. files) can't
+/// implement this.
+///
+/// ```rust
+/// # use storage_traits::{BorrowableStorage, SliceStorage};
+/// # use typenum::consts::U4;
+/// let mut words = [0u8; 12];
+/// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+///
+/// storage.sector_mut(1).unwrap()[2] = 0xAB;
+/// assert_eq!(storage.sector_ref(1).unwrap(), &[0, 0, 0xAB, 0]);
+/// assert!(storage.sector_ref(3).is_err());
+///
+/// assert_eq!(words[6], 0xAB);
+/// ```
+pub trait BorrowableStorage: Storage {
+    /// Borrows the sector at `sector_idx`.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    fn sector_ref(&self, sector_idx: usize) -> Result<&[Self::Word], ReadError<Self::ReadErr>>;
+
+    /// Mutably borrows the sector at `sector_idx`; writes through the returned
+    /// slice modify the storage directly.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    fn sector_mut(&mut self, sector_idx: usize) -> Result<&mut [Self::Word], WriteError<Self::WriteErr>>;
+}
+
+
+/// For storage mediums that can write _pages_: chunks that are smaller than a
+/// sector but larger than a word (i.e. NOR and NAND flash).
+///
+/// Implementors should also override [`Storage::write_page_size`] so that
+/// generic code can pick a write strategy without needing this trait.
+///
+/// ```rust
+/// # use storage_traits::{PageWritable, Storage, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::{U64, U512};
+/// # use typenum::marker_traits::Unsigned;
+/// #[derive(Debug)]
+/// struct Nor(Vec<u8>);
+///
+/// impl Storage for Nor {
+///     // ...
+/// #   type Word = u8; type SECTOR_SIZE = U512; type ReadErr = (); type WriteErr = ();
+/// #   fn capacity(&self) -> usize { self.0.len() / 512 }
+/// #   fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U512>) -> Result<(), ReadError<()>> {
+/// #       buf.copy_from_slice(&self.0[idx * 512..][..512]); Ok(())
+/// #   }
+/// #   fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U512>) -> Result<(), WriteError<()>> {
+/// #       self.0[idx * 512..][..512].copy_from_slice(words); Ok(())
+/// #   }
+///     fn write_page_size(&self) -> usize { U64::to_usize() }
+/// }
+///
+/// impl PageWritable for Nor {
+///     type WRITE_PAGE_SIZE = U64;
+///
+///     fn write_page(&mut self, page_idx: usize, words: &GenericArray<u8, U64>) -> Result<(), WriteError<()>> {
+///         if page_idx >= self.capacity_in_pages() {
+///             return Err(WriteError::OutOfRange { requested_offset: page_idx, max_offset: self.capacity_in_pages() });
+///         }
+///
+///         self.0[page_idx * 64..][..64].copy_from_slice(words);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut nor = Nor(vec![0; 2 * 512]);
+/// assert_eq!(nor.write_page_size(), 64);
+/// assert_eq!(nor.capacity_in_pages(), 16);
+///
+/// nor.write_page(9, &GenericArray::clone_from_slice(&[0xAA; 64])).unwrap();
+/// assert!(nor.0[..576].iter().all(|b| *b == 0));
+/// assert!(nor.0[576..640].iter().all(|b| *b == 0xAA));
+/// assert!(nor.0[640..].iter().all(|b| *b == 0));
+/// ```
+pub trait PageWritable: Storage {
+    /// The size of a page, in units of words. Sectors should be made up of a
+    /// whole number of pages.
+    #[allow(non_camel_case_types)]
+    type WRITE_PAGE_SIZE: ArrayLength<Self::Word>;
+
+    /// In units of pages.
+    fn capacity_in_pages(&self) -> usize {
+        self.capacity_in_words() / Self::WRITE_PAGE_SIZE::to_usize()
+    }
+
+    /// Writes out an entire page.
+    ///
+    /// `page_idx` must be in [0, `self.capacity_in_pages()`) for this to
+    /// succeed.
+    fn write_page(
+        &mut self,
+        page_idx: usize,
+        words: &GenericArray<Self::Word, Self::WRITE_PAGE_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>>;
+}
+
+
+/// For storage mediums with a device level write protect switch (i.e. the `WP`
+/// pin on SPI flash chips).
+///
+/// While write protection is on, implementors must reject all writes with
+/// [`WriteError::Protected`] _before_ touching the storage medium. Reads are
+/// unaffected.
+///
+/// The [`WriteProtect`](crate::adapters::WriteProtect) adapter provides this in
+/// software for storage mediums that don't have it.
+pub trait HardwareProtect: Storage {
+    /// Whether write protection is currently on.
+    fn is_write_protected(&self) -> bool;
+
+    /// Turns write protection on or off.
+    fn set_write_protect(&mut self, on: bool) -> Result<(), Self::WriteErr>;
+}
+
+
+/// For storage mediums that keep track of bad (i.e. worn out) sectors, like the
+/// bad block tables that flash devices maintain.
+///
+/// Reads and writes of sectors that are marked bad should fail with
+/// [`ReadError::BadBlock`] and [`WriteError::BadBlock`], so that wear leveling
+/// layers and filesystems can skip them.
+///
+/// The [`BadBlockTable`](crate::adapters::BadBlockTable) adapter provides this
+/// in software for any storage medium.
+pub trait BadBlockAware: Storage {
+    /// Whether the sector at `sector_idx` has been marked bad.
+    fn is_bad(&self, sector_idx: usize) -> bool;
+
+    /// Marks the sector at `sector_idx` as bad.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    fn mark_bad(&mut self, sector_idx: usize) -> Result<(), WriteError<Self::WriteErr>>;
+}
+
+
+/// For in-memory storage mediums that can cheaply save their entire state and
+/// roll back to it later (i.e. to model transactions in tests).
+///
+/// Restoring a snapshot discards every write made since it was taken.
+pub trait Snapshottable: Storage {
+    /// A saved copy of the storage medium's state.
+    type Snapshot;
+
+    /// Saves the current state of the storage medium.
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Rolls the storage medium back to the state saved in `snapshot`.
+    fn restore_snapshot(&mut self, snapshot: &Self::Snapshot);
+}
+
+
+/// For storage mediums with a status register that holds the detailed result
+/// of the last operation (i.e. hardware flash), so diagnostic tools can get at
+/// more than the immediate `Result` of an operation carries.
+///
+/// Software backends generally have nothing to add here and don't implement
+/// this.
+///
+/// ```rust
+/// # use storage_traits::{StatusReporting, Storage, errors::{ReadError, WriteError}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum FlashStatus { ProgramFailed { sector: usize } }
+///
+/// # #[derive(Debug, Default)]
+/// # struct Chip { status: Option<FlashStatus> }
+/// # impl Storage for Chip {
+/// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = FlashStatus;
+/// #     fn capacity(&self) -> usize { 4 }
+/// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> { Ok(()) }
+/// #     fn write_sector(&mut self, idx: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<FlashStatus>> {
+/// #         if idx == 3 {
+/// #             self.status = Some(FlashStatus::ProgramFailed { sector: idx });
+/// #             return Err(WriteError::Other(FlashStatus::ProgramFailed { sector: idx }));
+/// #         }
+/// #         Ok(())
+/// #     }
+/// # }
+/// impl StatusReporting for Chip {
+///     fn last_error(&self) -> Option<FlashStatus> { self.status }
+///     fn clear_status(&mut self) { self.status = None; }
+/// }
+///
+/// let mut chip = Chip::default();
+/// let data = GenericArray::from([0; 4]);
+///
+/// chip.write_sector(0, &data).unwrap();
+/// assert_eq!(chip.last_error(), None);
+///
+/// assert!(chip.write_sector(3, &data).is_err());
+/// chip.write_sector(1, &data).unwrap();
+/// assert_eq!(chip.last_error(), Some(FlashStatus::ProgramFailed { sector: 3 }));
+///
+/// chip.clear_status();
+/// assert_eq!(chip.last_error(), None);
+/// ```
+pub trait StatusReporting: Storage {
+    /// The error recorded by the last failed operation, if there is one.
+    ///
+    /// This persists across successful operations until it's cleared with
+    /// [`clear_status`](StatusReporting::clear_status).
+    fn last_error(&self) -> Option<Self::WriteErr>;
+
+    /// Clears the recorded error.
+    fn clear_status(&mut self);
+}
+
+/// For storage mediums (and adapters) that can say where a logical sector
+/// actually lives, i.e. to audit remapping layers for aliasing.
+///
+/// Direct backends map every sector onto itself. Adapters that remap sectors
+/// (i.e. [`Partition`](crate::adapters::Partition) or
+/// [`AtomicSector`](crate::adapters::AtomicSector)) map through to the
+/// physical sector of the storage medium at the bottom of the stack, so two
+/// logical sectors alias each other exactly when their physical sectors are
+/// equal.
+///
+/// ```rust
+/// # use storage_traits::{PhysicalMapping, SliceStorage, Storage, adapters::{AtomicSector, Partition}};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut words = [0u8; 4 * 8];
+/// let storage = Partition::new(SliceStorage::<u8, U4>::new(&mut words), 2, 5).unwrap();
+/// assert_eq!(storage.physical_sector(0), Some(2));
+///
+/// // Sector 0 of this lives in one of two slots; writes move it to the other:
+/// let mut storage = AtomicSector::new(storage).unwrap();
+/// assert_eq!(storage.physical_sector(0), Some(2 + 1));
+///
+/// storage.write_sector(0, &GenericArray::from([1, 2, 3, 4])).unwrap();
+/// assert_eq!(storage.physical_sector(0), Some(2 + 2));
+///
+/// storage.write_sector(0, &GenericArray::from([5, 6, 7, 8])).unwrap();
+/// assert_eq!(storage.physical_sector(0), Some(2 + 1));
+///
+/// assert_eq!(storage.physical_sector(storage.capacity()), None);
+/// ```
+pub trait PhysicalMapping: Storage {
+    /// The index of the physical sector that currently backs the logical
+    /// sector at `logical`.
+    ///
+    /// Returns `None` if `logical` isn't in [0, `self.capacity()`).
+    fn physical_sector(&self, logical: usize) -> Option<usize>;
+}
+
+/// For storage mediums that can make use of hints that sectors are no longer
+/// in use (i.e. TRIM/discard for SSDs or hole punching for sparse files), so
+/// that thin or sparse backends can reclaim the space backing them.
+///
+/// The contents of trimmed sectors are unspecified until they're written to
+/// again; in-memory backends usually forget them while files read back zeros.
+/// Trimming is only a hint so backends that can't do anything useful with it
+/// can rely on the default implementation, which only checks that the range is
+/// valid.
+pub trait Trimmable: Storage {
+    /// Hints that the `len` sectors starting at `start_sector` are no longer in
+    /// use.
+    ///
+    /// The whole range must be in [0, `self.capacity()`) for this to succeed.
+    fn trim(&mut self, start_sector: usize, len: usize) -> Result<(), WriteError<Self::WriteErr>> {
+        match start_sector.checked_add(len) {
+            Some(end) if end <= self.capacity() => Ok(()),
+            _ => Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(start_sector, len),
+                max_offset: self.capacity(),
+            }),
+        }
+    }
+}
+
+/// For storage mediums with a spare (out-of-band) area alongside every sector,
+/// like the spare bytes of each NAND flash page that hold ECC codes and bad
+/// block markers.
+///
+/// The out-of-band area of a sector is `OOB_SIZE` words long and is read and
+/// written separately from the sector's main data: writing one leaves the
+/// other untouched.
+///
+/// The [`WithOob`](crate::adapters::WithOob) adapter provides this in software
+/// for any storage medium, at the cost of some of its capacity.
+#[allow(non_camel_case_types)]
+pub trait OutOfBand: Storage {
+    /// The number of words in each sector's out-of-band area.
+    type OOB_SIZE: ArrayLength<Self::Word>;
+
+    /// Reads the out-of-band area of the sector at `sector_idx` into `buf`.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    fn read_oob(
+        &mut self,
+        sector_idx: usize,
+        buf: &mut GenericArray<Self::Word, Self::OOB_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>>;
+
+    /// Replaces the out-of-band area of the sector at `sector_idx` with
+    /// `data`.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    fn write_oob(
+        &mut self,
+        sector_idx: usize,
+        data: &GenericArray<Self::Word, Self::OOB_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>>;
 }
@@ -3,7 +3,6 @@
 //! Useful for embedded applications.
 
 #![forbid(
-    const_err,
     dead_code,
     improper_ctypes,
     non_shorthand_field_patterns,
@@ -11,7 +10,8 @@
     overflowing_literals,
     path_statements,
     patterns_in_fns_without_body,
-    private_in_public,
+    private_bounds,
+    private_interfaces,
     unconditional_recursion,
     unused_allocation,
     unused_lifetimes,
@@ -23,7 +23,7 @@
     unused,
     bad_style,
     missing_debug_implementations,
-    intra_doc_link_resolution_failure,
+    rustdoc::broken_intra_doc_links,
     // missing_docs, // TODO!
     unsafe_code,
     trivial_casts,
@@ -34,7 +34,10 @@
     unused_results,
     rust_2018_idioms
 )]
-#![doc(test(attr(deny(warnings))))]
+// `generic-array` 0.14.8+ marks everything as deprecated in favor of 1.x. We
+// stay on 0.14 (for now), so the modules and items that use it allow
+// `deprecated` locally rather than the whole crate allowing it.
+#![doc(test(attr(deny(warnings), allow(deprecated))))]
 #![doc(html_logo_url = "")] // TODO!
 
 // Mark the crate as no_std if the `no_std` feature is enabled.
@@ -52,11 +55,51 @@ pub use bytes::*;
 mod storage;
 pub use storage::*;
 
+mod storage_ext;
+pub use storage_ext::*;
+
 mod extensions;
 pub use extensions::*;
 
 pub mod errors;
 
+pub mod sizes;
+pub use sizes::{Sector256, Sector512, Sector1K, Sector2K, Sector4K, Sector8K};
+
+pub mod adapters;
+
+#[cfg(feature = "crc")]
+pub mod crc;
+
+#[cfg(feature = "testing")]
+using_std! { pub mod testing; }
+
+mod slice;
+pub use slice::*;
+
+mod array;
+pub use array::*;
+
+mod byte_store;
+pub use byte_store::*;
+
+mod split_word;
+pub use split_word::*;
+
+using_std! {
+    mod sparse;
+    pub use sparse::*;
+
+    mod dyn_sector;
+    pub use dyn_sector::*;
+
+    mod rope;
+    pub use rope::*;
+
+    mod ab_slots;
+    pub use ab_slots::*;
+}
+
 // TODO: move to its own file
 using_std! {
     use std::convert::TryInto;
@@ -65,13 +108,74 @@ using_std! {
     use std::marker::PhantomData;
     use std::path::Path;
 
+    #[allow(deprecated)]
     use generic_array::{ArrayLength, GenericArray};
 
-    #[allow(non_camel_case_types)]
+    /// A storage medium backed by a [`File`](File).
+    ///
+    /// Offsets into the file are always computed as `u64`s so sectors past
+    /// the 4 GiB mark of an image can be read and written on 32-bit hosts too,
+    /// as long as their sector indices fit in a `usize`. Word and byte counts
+    /// don't have that much headroom: on 32-bit hosts
+    /// [`capacity_in_words`](Storage::capacity_in_words) and
+    /// [`capacity_in_bytes`](Storage::capacity_in_bytes) saturate for such
+    /// images, and the word based traits can't reach words past `usize::MAX`.
+    /// Use [`capacity_in_bytes_u64`](FileBackedStorage::capacity_in_bytes_u64)
+    /// and [`write_word_u64`](FileBackedStorage::write_word_u64) instead. The
+    /// `u64` variants are only on this backend: widening the offsets in the
+    /// traits would cost every small, `no_std` user.
+    ///
+    /// ```rust
+    /// # use storage_traits::{FileBackedStorage, Storage};
+    /// # use generic_array::GenericArray;
+    /// # let path = std::env::temp_dir().join("storage-traits-large-image.bin");
+    /// # let _ = std::fs::remove_file(&path);
+    /// // 5 GiB, but sparse: only the blocks that are written take up space.
+    /// let size: u64 = 5 << 30;
+    /// let sectors = (size / 512) as usize;
+    /// # if cfg!(unix) {
+    /// let mut storage = FileBackedStorage::<u8>::new(&path, sectors).unwrap();
+    /// assert_eq!(storage.capacity_in_bytes_u64(), size);
+    ///
+    /// storage.write_sector(sectors - 1, &GenericArray::clone_from_slice(&[0xAB; 512])).unwrap();
+    /// storage.write_word_u64(size - 1, 0xCD).unwrap();
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(sectors - 1, &mut buf).unwrap();
+    /// assert_eq!(buf[0], 0xAB);
+    /// assert_eq!(buf[511], 0xCD);
+    ///
+    /// if cfg!(target_pointer_width = "32") {
+    ///     assert_eq!(storage.capacity_in_bytes(), usize::MAX);
+    /// }
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Wrapped in a [`ByteStore`], a `FileBackedStorage<u8>` works like a
+    /// persistent byte array:
+    ///
+    /// ```rust
+    /// # use storage_traits::{ByteStore, FileBackedStorage};
+    /// # let path = std::env::temp_dir().join("storage-traits-byte-store.bin");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut store = ByteStore::new(FileBackedStorage::<u8>::new(&path, 2).unwrap());
+    /// store.set(700, 0xCD).unwrap();
+    /// drop(store);
+    ///
+    /// let mut store = ByteStore::new(FileBackedStorage::<u8>::from_file(&path).unwrap());
+    /// assert_eq!(store.get(700).unwrap(), 0xCD);
+    /// assert_eq!(store.get(701).unwrap(), 0);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    ///
+    /// Unlike the in-memory backends this is _not_ [`Clone`]: there's no way to
+    /// duplicate the underlying [`File`](File) (and its contents) cheaply.
+    #[allow(non_camel_case_types, deprecated)]
     #[derive(Debug)]
     pub struct FileBackedStorage<
         Word = u8,
-        SECTOR_SIZE = typenum::consts::U512,
+        SECTOR_SIZE = sizes::DEFAULT_SECTOR,
     >
     where
         Word: AsBytes,
@@ -83,11 +187,42 @@ using_std! {
         // to deal with the possibility of the length of the `File` changing
         // underneath us and becoming not a multiple of the sector size.
         size_in_sectors: usize,
+        // The alignment (in bytes) the buffers handed to the OS need to have;
+        // 1 unless the file was opened with `O_DIRECT`.
+        io_align: usize,
         _s: PhantomData<(Word, SECTOR_SIZE)>,
     }
 
+    #[allow(deprecated)]
     impl<W: AsBytes, S: ArrayLength<W>> FileBackedStorage<W, S> {
-        // Fails if the file already exists.
+        /// Creates a new file at `path` that's `size_in_sectors` sectors long.
+        ///
+        /// Fails if the file already exists.
+        ///
+        /// The file is sized in bytes, so words larger than a byte take up
+        /// proportionally more space:
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// # let path = std::env::temp_dir().join("storage-traits-wide-words.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u32, U4>::new(&path, 3).unwrap();
+        /// assert_eq!(storage.file().metadata().unwrap().len(), 3 * 4 * 4);
+        ///
+        /// storage.write_sector(1, &GenericArray::from([1, 2, 3, 4])).unwrap();
+        /// storage.write_sector(2, &GenericArray::from([5, 6, 7, 8])).unwrap();
+        /// drop(storage);
+        ///
+        /// let mut storage = FileBackedStorage::<u32, U4>::from_file(&path).unwrap();
+        /// assert_eq!(storage.capacity(), 3);
+        ///
+        /// let mut buf = GenericArray::default();
+        /// storage.read_sector(1, &mut buf).unwrap();
+        /// assert_eq!(buf, GenericArray::from([1, 2, 3, 4]));
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
         pub fn new<P: AsRef<Path>>(path: P, size_in_sectors: usize) -> IoResult<Self> {
             let mut opts = OpenOptions::new();
 
@@ -98,7 +233,7 @@ using_std! {
                 .open(path)?;
 
             file.set_len(
-                S::to_u64()
+                (Self::sector_size_in_bytes() as u64)
                     .checked_mul(size_in_sectors.try_into().unwrap())
                     .unwrap()
             )?;
@@ -106,12 +241,14 @@ using_std! {
             Ok(Self {
                 file,
                 size_in_sectors,
+                io_align: 1,
                 _s: PhantomData,
             })
         }
 
         // Errors if the file does not have a size that's a multiple of the
-        // sector size.
+        // sector size. Block devices are sized with
+        // `probe_block_device_size`.
         pub fn from_file<P: AsRef<Path>>(path: P) -> IoResult<Self> {
             let mut opts = OpenOptions::new();
 
@@ -120,35 +257,462 @@ using_std! {
                 .write(true)
                 .open(path)?;
 
-            let len: usize = file.metadata()?.len().try_into().unwrap();
+            Self::try_from_open_file(file)
+        }
+
+        /// Wraps an already open [`File`](File) (i.e. one opened with specific
+        /// flags, a temporary file, or an inherited descriptor), using the
+        /// provided size. Like
+        /// [`from_file_with_explicit_size`](FileBackedStorage::from_file_with_explicit_size)
+        /// this trusts the size it's given.
+        ///
+        /// The file must be open for reading and writing.
+        pub fn from_open_file(file: File, size_in_sectors: usize) -> IoResult<Self> {
+            Ok(Self {
+                file,
+                size_in_sectors,
+                io_align: 1,
+                _s: PhantomData,
+            })
+        }
+
+        /// Wraps an already open [`File`](File), inferring the size from the
+        /// file's length.
+        ///
+        /// Like [`from_file`](FileBackedStorage::from_file), this errors if the
+        /// file does not have a size that's a multiple of the sector size.
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage};
+        /// # use std::fs::OpenOptions;
+        /// # let path = std::env::temp_dir().join("storage-traits-open-file.bin");
+        /// let file = OpenOptions::new()
+        ///     .read(true)
+        ///     .write(true)
+        ///     .create(true)
+        ///     .truncate(true)
+        ///     .open(&path)
+        ///     .unwrap();
+        /// file.set_len(3 * 512).unwrap();
+        ///
+        /// let storage = FileBackedStorage::<u8>::try_from_open_file(file).unwrap();
+        /// assert_eq!(storage.capacity(), 3);
+        ///
+        /// storage.file().set_len(3 * 512 + 1).unwrap();
+        /// assert!(FileBackedStorage::<u8>::try_from_open_file(storage.into_file()).is_err());
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        pub fn try_from_open_file(file: File) -> IoResult<Self> {
+            let size_in_sectors = Self::sectors_in(&file)?;
+            Self::from_open_file(file, size_in_sectors)
+        }
+
+        fn sectors_in(file: &File) -> IoResult<usize> {
+            let len = file_len(file)?;
+            let sector_size_in_bytes = Self::sector_size_in_bytes() as u64;
 
-            if let Some(0) = len.checked_rem(S::to_usize()) {
-                Ok(Self {
-                    file,
-                    size_in_sectors: (len.checked_div(S::to_usize()).unwrap()),
-                    _s: PhantomData,
-                })
+            if let Some(0) = len.checked_rem(sector_size_in_bytes) {
+                Ok((len / sector_size_in_bytes).try_into().unwrap())
             } else {
                 Err(Error::new(
                     ErrorKind::InvalidInput,
                     format!(
-                        "File length ({}) is not a multiple of the sector size ({}).",
+                        "File length ({}) is not a multiple of the sector size ({} bytes).",
                         len,
-                        S::to_usize(),
+                        sector_size_in_bytes,
                     ),
                 ))
             }
         }
 
-        // Necessary when the provided files are weird (i.e. block files).
+        /// Like [`from_file`](FileBackedStorage::from_file) but opens the file
+        /// with `O_DIRECT`, bypassing the page cache (i.e. for measuring the
+        /// latency of the actual device or for users that do their own
+        /// caching).
+        ///
+        /// `O_DIRECT` requires that the offsets, lengths, and memory addresses
+        /// of reads and writes are aligned (usually to the device's logical
+        /// block size). Sector I/O satisfies this as long as the sector size
+        /// is a multiple of the logical block size; this checks that sectors
+        /// are a multiple of 512 bytes, and buffers are aligned to 4096 bytes
+        /// internally. Word writes (i.e.
+        /// [`write_word_u64`](FileBackedStorage::write_word_u64)) are _not_
+        /// aligned and will generally fail.
+        ///
+        /// Not all filesystems support `O_DIRECT` (tmpfs, for example, does
+        /// not); opening a file on one fails with
+        /// [`ErrorKind::InvalidInput`](ErrorKind::InvalidInput).
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage};
+        /// # use generic_array::GenericArray;
+        /// # use std::io::ErrorKind;
+        /// # let path = std::env::current_dir().unwrap().join("target").join("storage-traits-direct.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// drop(FileBackedStorage::<u8>::new(&path, 4).unwrap());
+        ///
+        /// match FileBackedStorage::<u8>::from_file_direct(&path) {
+        ///     Ok(mut storage) => {
+        ///         let data = GenericArray::clone_from_slice(&[0x5A; 512]);
+        ///         storage.write_sector(2, &data).unwrap();
+        ///
+        ///         let mut buf = GenericArray::default();
+        ///         storage.read_sector(2, &mut buf).unwrap();
+        ///         assert_eq!(buf, data);
+        ///     }
+        ///     // The filesystem doesn't support `O_DIRECT`.
+        ///     Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidInput),
+        /// }
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64"),
+        ))]
+        pub fn from_file_direct<P: AsRef<Path>>(path: P) -> IoResult<Self> {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            const O_DIRECT: i32 = 0o40000;
+            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+            const O_DIRECT: i32 = 0o200000;
+
+            if Self::sector_size_in_bytes() % 512 != 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Sector size ({} bytes) is not a multiple of 512 bytes.",
+                        Self::sector_size_in_bytes(),
+                    ),
+                ));
+            }
+
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(O_DIRECT)
+                .open(path)?;
+
+            let mut f = Self::try_from_open_file(file)?;
+            f.io_align = 4096;
+
+            Ok(f)
+        }
+
+        // Necessary when the provided files are weird (i.e. block files on
+        // platforms where `probe_block_device_size` can't size them).
         pub fn from_file_with_explicit_size<P: AsRef<Path>>(path: P, size_in_sectors: usize) -> IoResult<Self> {
             let mut f = Self::from_file(path)?;
             f.size_in_sectors = size_in_sectors;
 
             Ok(f)
         }
+
+        /// Re-reads the length of the file and updates this storage's
+        /// capacity to match; for recovering after the file has been resized
+        /// (i.e. truncated) by someone else.
+        ///
+        /// The capacity is cached, so until this is called reads of sectors
+        /// past the new end of the file fail with
+        /// [`ReadError::InvalidData`](errors::ReadError::InvalidData) instead
+        /// of being reported as out of range.
+        ///
+        /// Errors (leaving the capacity as it was) if the new length is not a
+        /// multiple of the sector size.
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, errors::ReadError};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// # let path = std::env::temp_dir().join("storage-traits-revalidate.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u8, U4>::new(&path, 8).unwrap();
+        ///
+        /// // Someone else truncates the file:
+        /// std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(3 * 4).unwrap();
+        ///
+        /// let mut buf = GenericArray::default();
+        /// assert!(matches!(storage.read_sector(5, &mut buf), Err(ReadError::InvalidData { .. })));
+        ///
+        /// storage.revalidate().unwrap();
+        /// assert_eq!(storage.capacity(), 3);
+        /// assert!(matches!(storage.read_sector(5, &mut buf), Err(ReadError::OutOfRange { .. })));
+        ///
+        /// storage.file().set_len(3 * 4 + 1).unwrap();
+        /// assert!(storage.revalidate().is_err());
+        /// assert_eq!(storage.capacity(), 3);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        pub fn revalidate(&mut self) -> IoResult<()> {
+            self.size_in_sectors = Self::sectors_in(&self.file)?;
+
+            Ok(())
+        }
+
+        /// [`capacity_in_bytes`](Storage::capacity_in_bytes), as a `u64` so
+        /// that it doesn't saturate for images larger than 4 GiB on 32-bit
+        /// hosts.
+        pub fn capacity_in_bytes_u64(&self) -> u64 {
+            (self.size_in_sectors as u64)
+                .saturating_mul(S::to_u64())
+                .saturating_mul(W::NUM_BYTES as u64)
+        }
+
+        /// Writes `word` to the word at `addr`.
+        ///
+        /// This is [`WordWritable::write_word`] but with a `u64` address so
+        /// that words past `usize::MAX` can be reached on 32-bit hosts.
+        pub fn write_word_u64(&mut self, addr: u64, word: W) -> Result<(), errors::WriteError<Error>> {
+            let capacity_in_words = (self.size_in_sectors as u64)
+                .checked_mul(S::to_u64())
+                .unwrap();
+
+            if addr >= capacity_in_words {
+                return Err(errors::WriteError::OutOfRange {
+                    requested_offset: addr.try_into().unwrap_or(usize::MAX),
+                    max_offset: capacity_in_words.try_into().unwrap_or(usize::MAX),
+                });
+            }
+
+            // Move into place:
+            let _ = self.file.seek(SeekFrom::Start(
+                addr.checked_mul(W::NUM_BYTES as u64).unwrap()
+            ))?;
+
+            self.file.write_all(word.to().as_ref())?;
+
+            Ok(())
+        }
+
+        /// Reads the bytes of the sector at `sector_idx` exactly as they're
+        /// stored in the file into the start of `out`, without decoding them
+        /// into words (i.e. for forensic tools that don't want a bad
+        /// [`AsBytes`] impl to hide or reinterpret anything).
+        ///
+        /// `out` must be at least [`sector_size_in_bytes`](Storage::sector_size_in_bytes)
+        /// long; the rest of it is left alone. Like
+        /// [`read_sector`](Storage::read_sector), a sector that's cut short by
+        /// the end of the file is reported as
+        /// [`ReadError::InvalidData`](errors::ReadError::InvalidData).
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, errors::ReadError};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U2;
+        /// # let path = std::env::temp_dir().join("storage-traits-raw-bytes.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u32, U2>::new(&path, 2).unwrap();
+        /// storage.write_sector(1, &GenericArray::from([0x1122_3344, 0xAABB_CCDD])).unwrap();
+        ///
+        /// let mut raw = [0; 8];
+        /// storage.read_sector_raw_bytes(1, &mut raw).unwrap();
+        /// assert_eq!(raw, [0x44, 0x33, 0x22, 0x11, 0xDD, 0xCC, 0xBB, 0xAA]);
+        ///
+        /// assert!(matches!(
+        ///     storage.read_sector_raw_bytes(1, &mut [0; 7]),
+        ///     Err(ReadError::InvalidNumberOfBytes { bytes_given: 7, bytes_in_a_sector: 8 }),
+        /// ));
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        pub fn read_sector_raw_bytes(&mut self, sector_idx: usize, out: &mut [u8]) -> Result<(), errors::ReadError<Error>> {
+            if sector_idx >= self.size_in_sectors {
+                return Err(errors::ReadError::OutOfRange {
+                    requested_offset: sector_idx,
+                    max_offset: self.size_in_sectors
+                });
+            }
+
+            let sector_size_in_bytes = Self::sector_size_in_bytes();
+            if out.len() < sector_size_in_bytes {
+                return Err(errors::ReadError::InvalidNumberOfBytes {
+                    bytes_given: out.len(),
+                    bytes_in_a_sector: sector_size_in_bytes,
+                });
+            }
+
+            let _ = self.file.seek(SeekFrom::Start(Self::sector_start(sector_idx)))?;
+
+            let (mut backing, offset) = self.io_buffer(sector_size_in_bytes);
+            let buf = &mut backing[offset..][..sector_size_in_bytes];
+
+            let read = self.read_full(buf)?;
+            if read < sector_size_in_bytes {
+                return Err(errors::ReadError::InvalidData {
+                    offset: sector_idx * S::to_usize() + read / W::NUM_BYTES.max(1),
+                });
+            }
+
+            out[..sector_size_in_bytes].copy_from_slice(buf);
+            Ok(())
+        }
+
+        // A zeroed buffer of `len` bytes, aligned to `io_align`. The backing
+        // `Vec` is returned along with the offset of the buffer in it.
+        fn io_buffer(&self, len: usize) -> (Vec<u8>, usize) {
+            let buf = vec![0; len + self.io_align - 1];
+            let offset = buf.as_ptr().align_offset(self.io_align);
+
+            (buf, offset)
+        }
+
+        // Reads into `buf` until it's full or the end of the file is reached,
+        // returning the number of bytes read (`read_exact`, but reporting how
+        // far it got instead of failing at the end of the file).
+        fn read_full(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            let mut read = 0;
+
+            while read < buf.len() {
+                match self.file.read(&mut buf[read..]) {
+                    Ok(0) => break,
+                    Ok(n) => read += n,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok(read)
+        }
+
+        // Decodes the bytes of the sector at `sector_idx` into `buffer`. Bytes
+        // that are missing (i.e. past the end of a truncated file) are
+        // reported as invalid data.
+        fn decode_sector(
+            sector_idx: usize,
+            mut bytes: &[u8],
+            buffer: &mut GenericArray<W, S>,
+        ) -> Result<(), errors::ReadError<Error>> {
+            for idx in 0..(S::to_usize()) {
+                let (word, remaining) = match AsBytes::from(bytes) {
+                    Some(decoded) => decoded,
+                    None => return Err(errors::ReadError::InvalidData {
+                        offset: sector_idx * S::to_usize() + idx,
+                    }),
+                };
+
+                buffer.as_mut_slice()[idx] = word;
+                bytes = remaining;
+            }
+
+            Ok(())
+        }
+
+        fn sector_start(sector_idx: usize) -> u64 {
+            (sector_idx as u64)
+                .checked_mul(Self::sector_size_in_bytes() as u64)
+                .unwrap()
+        }
+
+        /// Borrows the underlying [`File`](File) (i.e. to query its metadata).
+        pub fn file(&self) -> &File {
+            &self.file
+        }
+
+        /// Consumes the storage and returns the underlying [`File`](File) (i.e.
+        /// to `fsync` it or to close it explicitly).
+        ///
+        /// ```rust
+        /// # use storage_traits::FileBackedStorage;
+        /// # let path = std::env::temp_dir().join("storage-traits-into-file.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let storage = FileBackedStorage::<u8>::new(&path, 4).unwrap();
+        /// let file = storage.into_file();
+        ///
+        /// file.sync_all().unwrap();
+        /// assert_eq!(file.metadata().unwrap().len(), 4 * 512);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        pub fn into_file(self) -> File {
+            self.file
+        }
+    }
+
+    /// Returns the size in bytes of the file or block device at `path`.
+    ///
+    /// For regular files this is the file's length. Block devices often report
+    /// a length of 0, so on Linux with the `block_device` feature enabled their
+    /// size is asked for with the `BLKGETSIZE64` ioctl instead; elsewhere (and
+    /// on architectures we don't know the ioctl number for) this falls back to
+    /// the reported length.
+    /// [`FileBackedStorage::from_file`] uses this to size the storage.
+    ///
+    /// ```rust
+    /// # use storage_traits::probe_block_device_size;
+    /// # let path = std::env::temp_dir().join("storage-traits-probe.bin");
+    /// std::fs::write(&path, [0u8; 1536]).unwrap();
+    /// assert_eq!(probe_block_device_size(&path).unwrap(), 1536);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn probe_block_device_size<P: AsRef<Path>>(path: P) -> IoResult<u64> {
+        file_len(&File::open(path)?)
+    }
+
+    fn file_len(file: &File) -> IoResult<u64> {
+        let metadata = file.metadata()?;
+
+        #[cfg(all(feature = "block_device", target_os = "linux", any(
+            target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64",
+            target_arch = "riscv32", target_arch = "riscv64", target_arch = "s390x", target_arch = "loongarch64",
+            target_arch = "powerpc", target_arch = "powerpc64", target_arch = "mips", target_arch = "mips64",
+            target_arch = "sparc", target_arch = "sparc64",
+        )))]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            if metadata.file_type().is_block_device() {
+                return block_device_size(file);
+            }
+        }
+
+        Ok(metadata.len())
+    }
+
+    #[cfg(all(feature = "block_device", target_os = "linux", any(
+        target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64",
+        target_arch = "riscv32", target_arch = "riscv64", target_arch = "s390x", target_arch = "loongarch64",
+        target_arch = "powerpc", target_arch = "powerpc64", target_arch = "mips", target_arch = "mips64",
+        target_arch = "sparc", target_arch = "sparc64",
+    )))]
+    #[allow(unsafe_code)]
+    fn block_device_size(file: &File) -> IoResult<u64> {
+        use std::os::raw::{c_int, c_ulong};
+        use std::os::unix::io::AsRawFd;
+
+        extern "C" {
+            fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        }
+
+        // `_IOR(0x12, 114, size_t)`. powerpc, mips and sparc use 13 bits for
+        // the size (and 3 for the direction) instead of the generic 14 (and 2)
+        // so the direction bits (`_IOC_READ` is 2 on both) start lower.
+        #[cfg(not(any(
+            target_arch = "powerpc", target_arch = "powerpc64", target_arch = "mips", target_arch = "mips64",
+            target_arch = "sparc", target_arch = "sparc64",
+        )))]
+        const IOC_DIRSHIFT: c_ulong = 30;
+        #[cfg(any(
+            target_arch = "powerpc", target_arch = "powerpc64", target_arch = "mips", target_arch = "mips64",
+            target_arch = "sparc", target_arch = "sparc64",
+        ))]
+        const IOC_DIRSHIFT: c_ulong = 29;
+
+        const BLKGETSIZE64: c_ulong = (2 << IOC_DIRSHIFT)
+            | ((size_of::<usize>() as c_ulong) << 16)
+            | (0x12 << 8)
+            | 114;
+
+        let mut size: u64 = 0;
+        let size_ptr: *mut u64 = &mut size;
+
+        // Safety: `BLKGETSIZE64` writes a single `u64` through the pointer,
+        // which is valid for the duration of the call.
+        if unsafe { ioctl(file.as_raw_fd(), BLKGETSIZE64, size_ptr) } == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(size)
     }
 
+    #[allow(deprecated)]
     impl<W: AsBytes, S: ArrayLength<W>> Storage for FileBackedStorage<W, S> {
         type Word = W;
         type SECTOR_SIZE = S;
@@ -160,6 +724,69 @@ using_std! {
             self.size_in_sectors
         }
 
+        /// Larger reads and writes amortize the cost of the syscalls (and
+        /// seeks); 128 sectors is 64 KiB with the default sector size.
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, adapters::Partition};
+        /// # let path = std::env::temp_dir().join("storage-traits-chunk-size.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let storage = FileBackedStorage::<u8>::new(&path, 256).unwrap();
+        /// assert_eq!(storage.optimal_chunk_sectors(), 128);
+        ///
+        /// // Adapters pass the hint through:
+        /// let partition = Partition::new(storage, 16, 64).unwrap();
+        /// assert_eq!(partition.optimal_chunk_sectors(), 128);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        fn optimal_chunk_sectors(&self) -> usize {
+            128
+        }
+
+        /// Sectors are laid out back to back from the start of the file.
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, adapters::Partition};
+        /// # let path = std::env::temp_dir().join("storage-traits-byte-offset.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let storage = FileBackedStorage::<u8>::new(&path, 32).unwrap();
+        /// assert_eq!(storage.sector_byte_offset(3), Some(3 * 512));
+        /// assert_eq!(storage.sector_byte_offset(32), None);
+        ///
+        /// // A partition starting 4096 bytes into the file:
+        /// let partition = Partition::new(storage, 4096 / 512, 8).unwrap();
+        /// assert_eq!(partition.sector_byte_offset(2), Some(4096 + 2 * 512));
+        /// assert_eq!(partition.sector_byte_offset(8), None);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+            if sector_idx >= self.size_in_sectors {
+                return None;
+            }
+
+            (sector_idx as u64).checked_mul(Self::sector_size_in_bytes() as u64)
+        }
+
+        /// Sectors that can't be decoded (i.e. because the file was truncated
+        /// after it was opened) produce a [`ReadError::InvalidData`](errors::ReadError::InvalidData)
+        /// for the first word that couldn't be decoded:
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, errors::ReadError};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// # let path = std::env::temp_dir().join("storage-traits-truncated.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u32, U4>::new(&path, 2).unwrap();
+        ///
+        /// // Cut the file off partway through the second word of sector 1:
+        /// storage.file().set_len(16 + 6).unwrap();
+        ///
+        /// let mut buf = GenericArray::default();
+        /// assert!(storage.read_sector(0, &mut buf).is_ok());
+        /// assert!(matches!(storage.read_sector(1, &mut buf), Err(ReadError::InvalidData { offset: 5 })));
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
         fn read_sector(
             &mut self,
             sector_idx: usize,
@@ -173,26 +800,53 @@ using_std! {
             }
 
             // Move into place:
-            let _ = self.file.seek(SeekFrom::Start(
-                sector_idx.checked_mul(S::to_usize()).unwrap().try_into().unwrap()
-            ))?;
+            let _ = self.file.seek(SeekFrom::Start(Self::sector_start(sector_idx)))?;
 
             // Do the read.
             // TODO: do better than this; we should be able to find a way to
             // do this without the intermediate buffer. Probably using unsafe.
-            let sector_size_in_bytes = S::to_usize() * W::NUM_BYTES;
-            let mut buf: Vec<u8> = Vec::with_capacity(sector_size_in_bytes);
-            buf.resize(sector_size_in_bytes, 0);
+            let sector_size_in_bytes = Self::sector_size_in_bytes();
+            let (mut backing, offset) = self.io_buffer(sector_size_in_bytes);
+            let buf = &mut backing[offset..][..sector_size_in_bytes];
 
-            assert_eq!(sector_size_in_bytes, self.file.read(&mut buf)?);
+            // Hitting the end of the file means it was truncated out from
+            // under us.
+            let read = self.read_full(buf)?;
 
             // Copy into the actual buffer...
-            let mut buf = buf.as_slice();
-            for idx in 0..(S::to_usize()) {
-                let (word, remaining) = AsBytes::from(buf).unwrap();
+            Self::decode_sector(sector_idx, &buf[..read], buffer)
+        }
 
-                buffer.as_mut_slice()[idx] = word;
-                buf = remaining;
+        /// Reads the whole run of sectors with one seek and one (buffered)
+        /// read.
+        fn read_sectors(
+            &mut self,
+            starting_sector_idx: usize,
+            sectors: &mut [GenericArray<W, S>],
+        ) -> Result<(), errors::ReadError<Error>> {
+            match starting_sector_idx.checked_add(sectors.len()) {
+                Some(end) if end <= self.size_in_sectors => {},
+                _ => return Err(errors::ReadError::OutOfRange {
+                    requested_offset: errors::last_offset(starting_sector_idx, sectors.len()),
+                    max_offset: self.size_in_sectors,
+                }),
+            }
+
+            let _ = self.file.seek(SeekFrom::Start(Self::sector_start(starting_sector_idx)))?;
+
+            let sector_size_in_bytes = Self::sector_size_in_bytes();
+            let len = sector_size_in_bytes * sectors.len();
+            let (mut backing, offset) = self.io_buffer(len);
+            let buf = &mut backing[offset..][..len];
+
+            let read = self.read_full(buf)?;
+            let buf = &buf[..read];
+
+            for (idx, sector) in sectors.iter_mut().enumerate() {
+                let start = (idx * sector_size_in_bytes).min(buf.len());
+                let end = (start + sector_size_in_bytes).min(buf.len());
+
+                Self::decode_sector(starting_sector_idx + idx, &buf[start..end], sector)?;
             }
 
             Ok(())
@@ -211,26 +865,163 @@ using_std! {
             }
 
             // Move into place:
-            let _ = self.file.seek(SeekFrom::Start(
-                sector_idx.checked_mul(S::to_usize()).unwrap().try_into().unwrap()
-            ))?;
+            let _ = self.file.seek(SeekFrom::Start(Self::sector_start(sector_idx)))?;
 
             // Do the write.
             // TODO: do better than this; we should be able to find a way to
             // do this without the intermediate buffer. Probably using unsafe.
-            let sector_size_in_bytes = S::to_usize() * W::NUM_BYTES;
-            let mut buf: Vec<u8> = Vec::with_capacity(sector_size_in_bytes);
+            let sector_size_in_bytes = Self::sector_size_in_bytes();
+            let (mut backing, offset) = self.io_buffer(sector_size_in_bytes);
+            let buf = &mut backing[offset..][..sector_size_in_bytes];
 
-            for word in words.iter() {
-                for bytes in word.to().as_ref().iter() {
-                    buf.push(*bytes);
-                }
+            for (word, chunk) in words.iter().zip(buf.chunks_mut(W::NUM_BYTES.max(1))) {
+                chunk.copy_from_slice(word.to().as_ref());
             }
 
-            assert_eq!(sector_size_in_bytes, buf.len());
-
             // Actually do the write:
-            assert_eq!(sector_size_in_bytes, self.file.write(&buf)?);
+            self.file.write_all(buf)?;
+
+            Ok(())
+        }
+    }
+
+    #[allow(deprecated)]
+    impl<W: AsBytes, S: ArrayLength<W>> PhysicalMapping for FileBackedStorage<W, S> {
+        fn physical_sector(&self, logical: usize) -> Option<usize> {
+            identity_mapping(self, logical)
+        }
+    }
+
+    /// On Linux with the `punch_hole` feature enabled, trimming punches a hole
+    /// in the file (`fallocate(FALLOC_FL_PUNCH_HOLE)`), freeing the disk space
+    /// behind the trimmed sectors; they read back as zeros afterwards. The
+    /// file's length doesn't change. Elsewhere (and on filesystems that can't
+    /// punch holes) this only checks bounds.
+    ///
+    /// ```rust
+    /// # use storage_traits::{FileBackedStorage, Storage, Trimmable};
+    /// # use generic_array::GenericArray;
+    /// # let path = std::env::temp_dir().join("storage-traits-trim.bin");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut storage = FileBackedStorage::<u8>::new(&path, 4).unwrap();
+    /// for idx in 0..4 {
+    ///     storage.write_sector(idx, &GenericArray::clone_from_slice(&[0xAB; 512])).unwrap();
+    /// }
+    ///
+    /// storage.trim(1, 2).unwrap();
+    /// assert_eq!(storage.file().metadata().unwrap().len(), 4 * 512);
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(3, &mut buf).unwrap();
+    /// assert_eq!(buf[0], 0xAB);
+    ///
+    /// assert!(storage.trim(3, 2).is_err());
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[allow(deprecated)]
+    impl<W: AsBytes, S: ArrayLength<W>> Trimmable for FileBackedStorage<W, S> {
+        fn trim(&mut self, start_sector: usize, len: usize) -> Result<(), errors::WriteError<Error>> {
+            match start_sector.checked_add(len) {
+                Some(end) if end <= self.capacity() => {},
+                _ => return Err(errors::WriteError::OutOfRange {
+                    requested_offset: errors::last_offset(start_sector, len),
+                    max_offset: self.capacity(),
+                }),
+            }
+
+            #[cfg(all(feature = "punch_hole", target_os = "linux"))]
+            {
+                let sector_bytes = Self::sector_size_in_bytes() as u64;
+                punch_hole(&self.file, start_sector as u64 * sector_bytes, len as u64 * sector_bytes)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[cfg(all(feature = "punch_hole", target_os = "linux"))]
+    #[allow(unsafe_code)]
+    fn punch_hole(file: &File, offset: u64, len: u64) -> IoResult<()> {
+        use std::convert::TryFrom;
+        use std::os::raw::c_int;
+        use std::os::unix::io::AsRawFd;
+
+        // glibc's `fallocate` takes a 32-bit `off_t` on 32-bit targets;
+        // `fallocate64` always takes 64-bit offsets. musl's `off_t` is always
+        // 64 bits wide.
+        extern "C" {
+            #[cfg_attr(target_env = "gnu", link_name = "fallocate64")]
+            fn fallocate(fd: c_int, mode: c_int, offset: i64, len: i64) -> c_int;
+        }
+
+        const FALLOC_FL_KEEP_SIZE: c_int = 0x01;
+        const FALLOC_FL_PUNCH_HOLE: c_int = 0x02;
+
+        #[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+        const EOPNOTSUPP: i32 = 122;
+        #[cfg(any(target_arch = "sparc", target_arch = "sparc64"))]
+        const EOPNOTSUPP: i32 = 45;
+        #[cfg(not(any(
+            target_arch = "mips", target_arch = "mips64", target_arch = "sparc", target_arch = "sparc64",
+        )))]
+        const EOPNOTSUPP: i32 = 95;
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        let too_big = |_| Error::new(ErrorKind::InvalidInput, "Hole doesn't fit in an `off_t`.");
+        let (offset, len) = (i64::try_from(offset).map_err(too_big)?, i64::try_from(len).map_err(too_big)?);
+
+        // Safety: `fallocate` only takes plain integers.
+        if unsafe { fallocate(file.as_raw_fd(), FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE, offset, len) } == -1 {
+            let err = Error::last_os_error();
+
+            // Trimming is only a hint; filesystems that can't punch holes
+            // (`EOPNOTSUPP`) just keep the data.
+            if err.raw_os_error() != Some(EOPNOTSUPP) {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[allow(deprecated)]
+    impl<W: AsBytes, S: ArrayLength<W>> WordWritable for FileBackedStorage<W, S> {
+        fn write_word(&mut self, addr: usize, word: W) -> Result<(), errors::WriteError<Error>> {
+            if addr >= self.capacity_in_words() {
+                return Err(errors::WriteError::OutOfRange {
+                    requested_offset: addr,
+                    max_offset: self.capacity_in_words(),
+                });
+            }
+
+            self.write_word_u64(addr as u64, word)
+        }
+
+        /// Flushes the underlying [`File`](File) and waits for its contents to
+        /// reach the disk.
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, WordWritable};
+        /// # use generic_array::GenericArray;
+        /// # let path = std::env::temp_dir().join("storage-traits-flush-words.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u8>::new(&path, 2).unwrap();
+        /// storage.write_word(513, 0xAB).unwrap();
+        /// storage.flush_words().unwrap();
+        /// drop(storage);
+        ///
+        /// let mut storage = FileBackedStorage::<u8>::from_file(&path).unwrap();
+        /// let mut buf = GenericArray::default();
+        /// storage.read_sector(1, &mut buf).unwrap();
+        /// assert_eq!(buf[1], 0xAB);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        fn flush_words(&mut self) -> Result<(), errors::WriteError<Error>> {
+            self.file.flush()?;
+            self.file.sync_data()?;
 
             Ok(())
         }
@@ -0,0 +1,326 @@
+//! Home of the [`RopeStorage`](RopeStorage) backend.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, PhysicalMapping, Snapshottable, Storage};
+use super::errors::{ReadError, WriteError};
+
+use std::convert::Infallible;
+
+use generic_array::{ArrayLength, GenericArray};
+
+/// The most sectors a freshly inserted chunk holds.
+const CHUNK_SIZE: usize = 64;
+
+/// An in-memory storage medium that supports inserting and removing sectors
+/// anywhere (i.e. for tools that resize partitions in disk images).
+///
+/// Sectors are kept in chunks of (at most) 64 sectors so structural edits only
+/// have to shuffle chunks around instead of moving every sector after the edit;
+/// adjacent chunks that fit in one are merged after each edit so repeated edits
+/// don't fragment the medium. This is optimized for those edits, _not_ for raw
+/// throughput: finding a sector is a binary search over the chunks, so plain
+/// reads and writes are slower than they'd be with a flat buffer.
+///
+/// ```rust
+/// # use storage_traits::{RopeStorage, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = RopeStorage::<u8, U4>::new(4);
+/// for idx in 0..4 {
+///     storage.write_sector(idx, &GenericArray::from([idx as u8; 4])).unwrap();
+/// }
+///
+/// storage.insert_sectors(2, 2).unwrap();
+/// assert_eq!(storage.capacity(), 6);
+///
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(2, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0; 4]));
+/// storage.read_sector(4, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([2; 4]));
+/// storage.read_sector(5, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([3; 4]));
+///
+/// storage.remove_sectors(1, 3).unwrap();
+/// assert_eq!(storage.capacity(), 3);
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([2; 4]));
+/// ```
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub struct RopeStorage<
+    Word = u8,
+    SECTOR_SIZE = typenum::consts::U512,
+>
+where
+    Word: AsBytes,
+    SECTOR_SIZE: ArrayLength<Word>,
+{
+    // Never contains empty chunks.
+    chunks: Vec<Vec<GenericArray<Word, SECTOR_SIZE>>>,
+    // `starts[i]` is the index of the first sector in `chunks[i]`.
+    starts: Vec<usize>,
+    size_in_sectors: usize,
+}
+
+impl<W: AsBytes, S: ArrayLength<W>> RopeStorage<W, S> {
+    /// Creates a storage medium with `size_in_sectors` sectors, all of which
+    /// are filled with `Word::default()`.
+    pub fn new(size_in_sectors: usize) -> Self
+    where
+        W: Default,
+    {
+        let mut storage = Self {
+            chunks: Self::blank_chunks(size_in_sectors),
+            starts: Vec::new(),
+            size_in_sectors,
+        };
+
+        let chunk_count = storage.chunks.len();
+        storage.reindex(0, chunk_count);
+        storage
+    }
+
+    /// Inserts `count` sectors (filled with `Word::default()`) before the
+    /// sector at `sector_idx`; the sectors at and after `sector_idx` move up by
+    /// `count`.
+    ///
+    /// `sector_idx` can be the capacity in which case the sectors are
+    /// appended.
+    ///
+    /// ```rust
+    /// # use storage_traits::{RopeStorage, Storage};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U1;
+    /// let mut storage = RopeStorage::<u8, U1>::new(100);
+    /// storage.write_sector(99, &GenericArray::from([7])).unwrap();
+    ///
+    /// // Lots of small edits that split (and re-merge) chunks:
+    /// for idx in 0..50 {
+    ///     storage.insert_sectors(idx * 3 + 1, 3).unwrap();
+    ///     storage.remove_sectors(idx * 3, 1).unwrap();
+    /// }
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(199, &mut buf).unwrap();
+    /// assert_eq!((storage.capacity(), buf[0]), (200, 7));
+    /// ```
+    pub fn insert_sectors(&mut self, sector_idx: usize, count: usize) -> Result<(), WriteError<Infallible>>
+    where
+        W: Default,
+    {
+        if sector_idx > self.size_in_sectors {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.size_in_sectors,
+            });
+        }
+
+        // `touched` is the first chunk the edit changes: the one we split, if
+        // we split one.
+        let mut new_chunks = Self::blank_chunks(count);
+        let (touched, at) = match self.locate(sector_idx) {
+            Some((chunk_idx, 0)) => (chunk_idx, chunk_idx),
+            Some((chunk_idx, offset)) => {
+                new_chunks.push(self.chunks[chunk_idx].split_off(offset));
+                (chunk_idx, chunk_idx + 1)
+            }
+            None => (self.chunks.len(), self.chunks.len()),
+        };
+
+        let inserted = new_chunks.len();
+        let _ = self.chunks.splice(at..at, new_chunks);
+        self.size_in_sectors += count;
+        self.reindex(touched, at + inserted);
+
+        Ok(())
+    }
+
+    /// Removes the `count` sectors starting at `sector_idx`; the sectors after
+    /// them move down by `count`.
+    pub fn remove_sectors(&mut self, sector_idx: usize, count: usize) -> Result<(), WriteError<Infallible>> {
+        match sector_idx.checked_add(count) {
+            Some(end) if end <= self.size_in_sectors => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(sector_idx, count),
+                max_offset: self.size_in_sectors,
+            }),
+        }
+
+        let mut remaining = count;
+        if let Some((first, offset)) = self.locate(sector_idx) {
+            let mut chunk_idx = first;
+
+            // Trim the chunk the range starts in...
+            if offset != 0 {
+                let chunk = &mut self.chunks[chunk_idx];
+                let end = chunk.len().min(offset + remaining);
+                remaining -= end - offset;
+                let _ = chunk.drain(offset..end);
+                chunk_idx += 1;
+            }
+
+            // ...drop the chunks that are entirely covered...
+            let mut whole = chunk_idx;
+            while whole < self.chunks.len() && self.chunks[whole].len() <= remaining {
+                remaining -= self.chunks[whole].len();
+                whole += 1;
+            }
+            let _ = self.chunks.drain(chunk_idx..whole);
+
+            // ...and trim the one it ends in.
+            if remaining != 0 {
+                let _ = self.chunks[chunk_idx].drain(..remaining);
+            }
+
+            self.reindex(first, chunk_idx + 1);
+        }
+
+        self.size_in_sectors -= count;
+        Ok(())
+    }
+
+    fn blank_chunks(count: usize) -> Vec<Vec<GenericArray<W, S>>>
+    where
+        W: Default,
+    {
+        let mut chunks = Vec::with_capacity(count.div_ceil(CHUNK_SIZE));
+        let mut remaining = count;
+
+        while remaining != 0 {
+            let len = remaining.min(CHUNK_SIZE);
+            chunks.push((0..len).map(|_| GenericArray::default()).collect());
+            remaining -= len;
+        }
+
+        chunks
+    }
+
+    // Called after every structural edit with the range of chunks the edit
+    // touched (which may run past the end): merges adjacent chunks that fit in
+    // one chunk (only the touched chunks and their neighbours can have become
+    // mergeable) and recomputes `starts` from the first touched chunk on.
+    fn reindex(&mut self, lo: usize, hi: usize) {
+        let lo = lo.saturating_sub(1).min(self.chunks.len());
+        let hi = hi.saturating_add(1).min(self.chunks.len()).max(lo);
+
+        let mut merged: Vec<Vec<GenericArray<W, S>>> = Vec::with_capacity(hi - lo);
+        for chunk in self.chunks.drain(lo..hi) {
+            match merged.last_mut() {
+                Some(last) if last.len() + chunk.len() <= CHUNK_SIZE => last.extend(chunk),
+                _ => merged.push(chunk),
+            }
+        }
+        let _ = self.chunks.splice(lo..lo, merged);
+
+        self.starts.truncate(lo);
+        let mut start = match lo.checked_sub(1) {
+            Some(prev) => self.starts[prev] + self.chunks[prev].len(),
+            None => 0,
+        };
+        for chunk in self.chunks[lo..].iter() {
+            self.starts.push(start);
+            start += chunk.len();
+        }
+    }
+
+    // Returns the chunk that holds `sector_idx` and the sector's offset within
+    // that chunk.
+    fn locate(&self, sector_idx: usize) -> Option<(usize, usize)> {
+        if sector_idx >= self.size_in_sectors {
+            return None;
+        }
+
+        // The last chunk that starts at or before `sector_idx`.
+        let chunk_idx = self.starts.partition_point(|&start| start <= sector_idx) - 1;
+        Some((chunk_idx, sector_idx - self.starts[chunk_idx]))
+    }
+}
+
+impl<W: AsBytes + Clone, S: ArrayLength<W>> Storage for RopeStorage<W, S> {
+    type Word = W;
+    type SECTOR_SIZE = S;
+
+    type ReadErr = Infallible;
+    type WriteErr = Infallible;
+
+    fn capacity(&self) -> usize {
+        self.size_in_sectors
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<W, S>,
+    ) -> Result<(), ReadError<Infallible>> {
+        match self.locate(sector_idx) {
+            Some((chunk_idx, offset)) => {
+                buffer.clone_from_slice(&self.chunks[chunk_idx][offset]);
+                Ok(())
+            }
+            None => Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.size_in_sectors,
+            }),
+        }
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<W, S>,
+    ) -> Result<(), WriteError<Infallible>> {
+        match self.locate(sector_idx) {
+            Some((chunk_idx, offset)) => {
+                self.chunks[chunk_idx][offset].clone_from_slice(words);
+                Ok(())
+            }
+            None => Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.size_in_sectors,
+            }),
+        }
+    }
+}
+
+impl<W: AsBytes + Clone, S: ArrayLength<W>> PhysicalMapping for RopeStorage<W, S> {
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        crate::extensions::identity_mapping(self, logical)
+    }
+}
+
+/// Snapshots are clones of the whole storage medium, structure included:
+/// restoring a snapshot also undoes sector insertions and removals.
+///
+/// ```rust
+/// # use storage_traits::{RopeStorage, Snapshottable, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = RopeStorage::<u8, U4>::new(3);
+/// storage.write_sector(1, &GenericArray::from([1; 4])).unwrap();
+///
+/// let before = storage.snapshot();
+/// storage.insert_sectors(0, 2).unwrap();
+/// storage.write_sector(3, &GenericArray::from([2; 4])).unwrap();
+///
+/// // The intermediate state:
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(3, &mut buf).unwrap();
+/// assert_eq!((storage.capacity(), buf), (5, GenericArray::from([2; 4])));
+///
+/// storage.restore_snapshot(&before);
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!((storage.capacity(), buf), (3, GenericArray::from([1; 4])));
+/// ```
+impl<W: AsBytes + Clone, S: ArrayLength<W>> Snapshottable for RopeStorage<W, S> {
+    type Snapshot = Self;
+
+    fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &Self) {
+        self.clone_from(snapshot);
+    }
+}
@@ -0,0 +1,52 @@
+//! Common sector sizes, for use as a [`Storage`](crate::Storage)'s
+//! `SECTOR_SIZE` without having to go through [`typenum`].
+//!
+//! These are in units of words (like `SECTOR_SIZE` itself); for storage mediums
+//! with byte sized words they're also the size in bytes.
+//!
+//! The `CamelCase` aliases (i.e. [`Sector4K`]) are also
+//! re-exported from the crate root:
+//!
+//! ```rust
+//! # use storage_traits::{SliceStorage, Storage, Sector4K, sizes::SECTOR_4K};
+//! let mut words = vec![0u8; 4096 * 2];
+//! let storage = SliceStorage::<u8, Sector4K>::new(&mut words);
+//! assert_eq!(storage.capacity(), 2);
+//!
+//! let storage: SliceStorage<'_, u8, SECTOR_4K> = storage;
+//! assert_eq!(storage.capacity_in_bytes(), 4096 * 2);
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use typenum::consts::{U256, U512, U1024, U2048, U4096, U8192};
+
+/// 256 words.
+pub type SECTOR_256 = U256;
+/// 512 words; the classic disk sector size.
+pub type SECTOR_512 = U512;
+/// 1024 words.
+pub type SECTOR_1K = U1024;
+/// 2048 words.
+pub type SECTOR_2K = U2048;
+/// 4096 words; the sector size of "Advanced Format" disks and a common flash
+/// erase block size.
+pub type SECTOR_4K = U4096;
+/// 8192 words.
+pub type SECTOR_8K = U8192;
+
+/// The default `SECTOR_SIZE` of `FileBackedStorage`.
+pub type DEFAULT_SECTOR = SECTOR_512;
+
+/// 256 words; see [`SECTOR_256`].
+pub type Sector256 = SECTOR_256;
+/// 512 words; see [`SECTOR_512`].
+pub type Sector512 = SECTOR_512;
+/// 1024 words; see [`SECTOR_1K`].
+pub type Sector1K = SECTOR_1K;
+/// 2048 words; see [`SECTOR_2K`].
+pub type Sector2K = SECTOR_2K;
+/// 4096 words; see [`SECTOR_4K`].
+pub type Sector4K = SECTOR_4K;
+/// 8192 words; see [`SECTOR_8K`].
+pub type Sector8K = SECTOR_8K;
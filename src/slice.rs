@@ -0,0 +1,257 @@
+//! Home of the [`SliceStorage`](SliceStorage) backend.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, BorrowableStorage, PhysicalMapping, Storage, Trimmable, WordReadable, WordWritable};
+use super::errors::{ReadError, WriteError};
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+
+use generic_array::{ArrayLength, GenericArray};
+
+/// A storage medium backed by a borrowed slice of words (i.e. a static buffer
+/// or a memory mapped region).
+///
+/// Any words past the last whole sector in the slice are ignored.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+pub struct SliceStorage<
+    'a,
+    Word = u8,
+    SECTOR_SIZE = typenum::consts::U512,
+>
+where
+    Word: AsBytes,
+    SECTOR_SIZE: ArrayLength<Word>,
+{
+    words: &'a mut [Word],
+    _s: PhantomData<SECTOR_SIZE>,
+}
+
+impl<'a, W: AsBytes, S: ArrayLength<W>> SliceStorage<'a, W, S> {
+    /// Wraps `words`.
+    pub fn new(words: &'a mut [W]) -> Self {
+        Self {
+            words,
+            _s: PhantomData,
+        }
+    }
+
+    /// Unwraps this storage, returning the underlying slice.
+    pub fn into_inner(self) -> &'a mut [W] {
+        self.words
+    }
+
+    fn sector_range(&self, sector_idx: usize) -> Option<core::ops::Range<usize>> {
+        if sector_idx < self.words.len() / S::to_usize() {
+            Some(Self::sector_range_unchecked(sector_idx))
+        } else {
+            None
+        }
+    }
+
+    fn sector_range_unchecked(sector_idx: usize) -> core::ops::Range<usize> {
+        let start = sector_idx * S::to_usize();
+        start..(start + S::to_usize())
+    }
+}
+
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> Storage for SliceStorage<'a, W, S> {
+    type Word = W;
+    type SECTOR_SIZE = S;
+
+    type ReadErr = Infallible;
+    type WriteErr = Infallible;
+
+    fn capacity(&self) -> usize {
+        self.words.len() / S::to_usize()
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<W, S>,
+    ) -> Result<(), ReadError<Infallible>> {
+        buffer.clone_from_slice(self.sector_ref(sector_idx)?);
+        Ok(())
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<W, S>,
+    ) -> Result<(), WriteError<Infallible>> {
+        self.sector_mut(sector_idx)?.clone_from_slice(words);
+        Ok(())
+    }
+
+    /// Skips the bounds check on the underlying slice.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u8; 4 * 3];
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+    /// let mut buf = GenericArray::default();
+    ///
+    /// for idx in 0..storage.capacity() {
+    ///     // Safety: `idx` is in range.
+    ///     unsafe {
+    ///         storage.write_sector_unchecked(idx, &GenericArray::from([idx as u8; 4])).unwrap();
+    ///         storage.read_sector_unchecked(idx, &mut buf).unwrap();
+    ///     }
+    ///     assert_eq!(buf, GenericArray::from([idx as u8; 4]));
+    /// }
+    /// ```
+    #[cfg(feature = "unchecked")]
+    #[allow(unsafe_code)]
+    unsafe fn read_sector_unchecked(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<W, S>,
+    ) -> Result<(), ReadError<Infallible>> {
+        // Safety: our caller guarantees that `sector_idx` is in range, so the
+        // whole sector is within `self.words`.
+        buffer.clone_from_slice(unsafe { self.words.get_unchecked(Self::sector_range_unchecked(sector_idx)) });
+        Ok(())
+    }
+
+    #[cfg(feature = "unchecked")]
+    #[allow(unsafe_code)]
+    unsafe fn write_sector_unchecked(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<W, S>,
+    ) -> Result<(), WriteError<Infallible>> {
+        // Safety: as above.
+        unsafe { self.words.get_unchecked_mut(Self::sector_range_unchecked(sector_idx)) }.clone_from_slice(words);
+        Ok(())
+    }
+}
+
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> WordReadable for SliceStorage<'a, W, S> {
+    fn read_word(&self, word_offset: usize) -> Result<W, ReadError<Infallible>> {
+        if word_offset >= self.capacity_in_words() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: word_offset,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        Ok(self.words[word_offset].clone())
+    }
+
+    fn read_words(
+        &mut self,
+        word_offset: usize,
+        buffer: &mut [W],
+    ) -> Result<(), ReadError<Infallible>> {
+        match word_offset.checked_add(buffer.len()) {
+            Some(end) if end <= self.capacity_in_words() => {
+                buffer.clone_from_slice(&self.words[word_offset..end]);
+                Ok(())
+            },
+            _ => Err(ReadError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, buffer.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+    }
+}
+
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> WordWritable for SliceStorage<'a, W, S> {
+    fn write_word(&mut self, addr: usize, word: W) -> Result<(), WriteError<Infallible>> {
+        if addr >= self.capacity_in_words() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: addr,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        self.words[addr] = word;
+        Ok(())
+    }
+
+    fn write_words(&mut self, word_offset: usize, words: &[W]) -> Result<(), WriteError<Infallible>> {
+        match word_offset.checked_add(words.len()) {
+            Some(end) if end <= self.capacity_in_words() => {
+                self.words[word_offset..end].clone_from_slice(words);
+                Ok(())
+            },
+            _ => Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, words.len()),
+                max_offset: self.capacity_in_words(),
+            }),
+        }
+    }
+}
+
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> PhysicalMapping for SliceStorage<'a, W, S> {
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        crate::extensions::identity_mapping(self, logical)
+    }
+}
+
+/// The slice can't give memory back so trimming does nothing.
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> Trimmable for SliceStorage<'a, W, S> { }
+
+impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> BorrowableStorage for SliceStorage<'a, W, S> {
+    fn sector_ref(&self, sector_idx: usize) -> Result<&[W], ReadError<Infallible>> {
+        match self.sector_range(sector_idx) {
+            Some(range) => Ok(&self.words[range]),
+            None => Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            }),
+        }
+    }
+
+    fn sector_mut(&mut self, sector_idx: usize) -> Result<&mut [W], WriteError<Infallible>> {
+        match self.sector_range(sector_idx) {
+            Some(range) => Ok(&mut self.words[range]),
+            None => Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            }),
+        }
+    }
+}
+
+using_std! {
+    use super::Snapshottable;
+
+    /// Snapshots are copies of the underlying slice (requires that `no_std` is
+    /// not enabled).
+    ///
+    /// Restoring copies the words back into the slice; this panics if the
+    /// snapshot was taken from a slice of a different length.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Snapshottable, Storage};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u16; 8];
+    /// let mut storage = SliceStorage::<u16, U4>::new(&mut words);
+    /// storage.write_sector(0, &GenericArray::from([1; 4])).unwrap();
+    ///
+    /// let before = storage.snapshot();
+    /// storage.write_sector(0, &GenericArray::from([2; 4])).unwrap();
+    /// storage.write_sector(1, &GenericArray::from([3; 4])).unwrap();
+    ///
+    /// storage.restore_snapshot(&before);
+    /// assert_eq!(words, [1, 1, 1, 1, 0, 0, 0, 0]);
+    /// ```
+    impl<'a, W: AsBytes + Clone, S: ArrayLength<W>> Snapshottable for SliceStorage<'a, W, S> {
+        type Snapshot = Vec<W>;
+
+        fn snapshot(&self) -> Vec<W> {
+            self.words.to_vec()
+        }
+
+        fn restore_snapshot(&mut self, snapshot: &Vec<W>) {
+            self.words.clone_from_slice(snapshot);
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Home of the [`SparseStorage`](SparseStorage) backend.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, InitTracking, PhysicalMapping, Snapshottable, Storage, Trimmable, UninitPolicy};
+use super::errors::{ReadError, WriteError};
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use generic_array::{ArrayLength, GenericArray};
+
+/// An in-memory storage medium that only holds the sectors that have actually
+/// been written to.
+///
+/// By default, reads of sectors that haven't been written to return
+/// [`ReadError::Uninitialized`]; this can be changed with
+/// [`with_uninit_policy`](SparseStorage::with_uninit_policy). Sectors that have been written to can be
+/// iterated over with [`InitTracking::written_sectors`]:
+///
+/// ```rust
+/// # use storage_traits::{InitTracking, SparseStorage, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = SparseStorage::<u8, U4>::new(1000);
+/// storage.write_sector(7, &GenericArray::from([7; 4])).unwrap();
+/// storage.write_sector(900, &GenericArray::from([9; 4])).unwrap();
+///
+/// let written: Vec<_> = storage.written_sectors().map(Result::unwrap).collect();
+/// assert_eq!(written, vec![
+///     (7, GenericArray::from([7; 4])),
+///     (900, GenericArray::from([9; 4])),
+/// ]);
+/// ```
+///
+/// Like the other in-memory backends ([`DynSectorStorage`](crate::DynSectorStorage)),
+/// this is [`Clone`]; cloning duplicates the written sectors so the clone can
+/// be used as a snapshot:
+///
+/// ```rust
+/// # use storage_traits::{SparseStorage, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut original = SparseStorage::<u8, U4>::new(4);
+/// original.write_sector(1, &GenericArray::from([1; 4])).unwrap();
+///
+/// let mut snapshot = original.clone();
+/// snapshot.write_sector(1, &GenericArray::from([2; 4])).unwrap();
+/// snapshot.write_sector(3, &GenericArray::from([3; 4])).unwrap();
+///
+/// let mut buf = GenericArray::default();
+/// original.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([1; 4]));
+/// assert!(original.read_sector(3, &mut buf).is_err());
+/// ```
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub struct SparseStorage<
+    Word = u8,
+    SECTOR_SIZE = typenum::consts::U512,
+>
+where
+    Word: AsBytes,
+    SECTOR_SIZE: ArrayLength<Word>,
+{
+    sectors: BTreeMap<usize, GenericArray<Word, SECTOR_SIZE>>,
+    size_in_sectors: usize,
+    uninit: UninitPolicy<Word>,
+}
+
+impl<W: AsBytes, S: ArrayLength<W>> SparseStorage<W, S> {
+    /// Creates an empty storage medium that's `size_in_sectors` sectors long.
+    pub fn new(size_in_sectors: usize) -> Self {
+        Self {
+            sectors: BTreeMap::new(),
+            size_in_sectors,
+            uninit: UninitPolicy::Error,
+        }
+    }
+
+    /// Creates a storage medium that's `size_in_sectors` sectors long with the
+    /// given sectors already written (i.e. for test fixtures).
+    ///
+    /// Fails if any of the sector indices are out of range. If an index shows
+    /// up more than once, the last sector given for it wins.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SparseStorage, Storage, errors::WriteError};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut storage = SparseStorage::<u8, U4>::from_sectors(16, vec![
+    ///     (0, GenericArray::from([1; 4])),
+    ///     (10, GenericArray::from([2; 4])),
+    /// ]).unwrap();
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(0, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([1; 4]));
+    /// storage.read_sector(10, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([2; 4]));
+    /// assert!(storage.read_sector(5, &mut buf).is_err());
+    ///
+    /// assert_eq!(
+    ///     SparseStorage::<u8, U4>::from_sectors(4, vec![(4, GenericArray::default())]).unwrap_err(),
+    ///     WriteError::OutOfRange { requested_offset: 4, max_offset: 4 },
+    /// );
+    /// ```
+    pub fn from_sectors<I>(size_in_sectors: usize, sectors: I) -> Result<Self, WriteError<Infallible>>
+    where
+        I: IntoIterator<Item = (usize, GenericArray<W, S>)>,
+    {
+        let mut storage = Self::new(size_in_sectors);
+
+        for (sector_idx, sector) in sectors {
+            if sector_idx >= size_in_sectors {
+                return Err(WriteError::OutOfRange {
+                    requested_offset: sector_idx,
+                    max_offset: size_in_sectors,
+                });
+            }
+
+            let _ = storage.sectors.insert(sector_idx, sector);
+        }
+
+        Ok(storage)
+    }
+
+    /// Sets what reads of sectors that haven't been written to do.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SparseStorage, Storage, UninitPolicy, errors::ReadError};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut buf = GenericArray::default();
+    ///
+    /// let mut storage = SparseStorage::<u16, U4>::new(8);
+    /// assert_eq!(storage.read_sector(2, &mut buf), Err(ReadError::Uninitialized { offset: 8 }));
+    ///
+    /// let mut storage = storage.with_uninit_policy(UninitPolicy::Zero);
+    /// storage.read_sector(2, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([0; 4]));
+    ///
+    /// let mut storage = storage.with_uninit_policy(UninitPolicy::EraseValue(0xFFFF));
+    /// storage.read_sector(2, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([0xFFFF; 4]));
+    /// ```
+    ///
+    /// To pick a fill value per read instead, keep the default policy and use
+    /// [`StorageExt::read_sector_or_default`](crate::StorageExt::read_sector_or_default):
+    ///
+    /// ```rust
+    /// # use storage_traits::{SparseStorage, Storage, StorageExt};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut storage = SparseStorage::<u16, U4>::new(8);
+    /// storage.write_sector(1, &GenericArray::from([1, 2, 3, 4])).unwrap();
+    ///
+    /// assert_eq!(storage.read_sector_or_default(1, 0xFFFF), Ok(GenericArray::from([1, 2, 3, 4])));
+    /// assert_eq!(storage.read_sector_or_default(2, 0xFFFF), Ok(GenericArray::from([0xFFFF; 4])));
+    /// assert_eq!(storage.read_sector_or_default(2, 0), Ok(GenericArray::from([0; 4])));
+    /// assert!(storage.read_sector_or_default(8, 0).is_err());
+    /// ```
+    pub fn with_uninit_policy(self, uninit: UninitPolicy<W>) -> Self {
+        Self { uninit, ..self }
+    }
+
+    /// Writes `pad` to every word of every sector that hasn't been written to
+    /// yet, producing a fully defined image (i.e. one that's ready to be
+    /// flashed).
+    ///
+    /// Afterwards every sector counts as written (see
+    /// [`InitTracking`](crate::InitTracking)) so the [`UninitPolicy`] no longer
+    /// has any effect. Note that this allocates every sector that was
+    /// previously left out.
+    ///
+    /// ```rust
+    /// # use storage_traits::{InitTracking, SparseStorage, Storage};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut storage = SparseStorage::<u8, U4>::new(3);
+    /// storage.write_sector(1, &GenericArray::from([1; 4])).unwrap();
+    ///
+    /// storage.finalize_with_pad(0xFF).unwrap();
+    /// assert!((0..3).all(|idx| storage.is_initialized(idx)));
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.read_sector(0, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([0xFF; 4]));
+    /// storage.read_sector(1, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([1; 4]));
+    /// ```
+    pub fn finalize_with_pad(&mut self, pad: W) -> Result<(), WriteError<Infallible>>
+    where
+        W: Clone,
+    {
+        let padding: GenericArray<W, S> = GenericArray::from_exact_iter(
+            std::iter::repeat_n(pad, S::to_usize())
+        ).unwrap();
+
+        for idx in 0..self.size_in_sectors {
+            let _ = self.sectors.entry(idx).or_insert_with(|| padding.clone());
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: AsBytes + Clone, S: ArrayLength<W>> Storage for SparseStorage<W, S> {
+    type Word = W;
+    type SECTOR_SIZE = S;
+
+    type ReadErr = Infallible;
+    type WriteErr = Infallible;
+
+    fn capacity(&self) -> usize {
+        self.size_in_sectors
+    }
+
+    fn read_sector(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<W, S>,
+    ) -> Result<(), ReadError<Infallible>> {
+        if sector_idx >= self.size_in_sectors {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.size_in_sectors,
+            });
+        }
+
+        match self.sectors.get(&sector_idx) {
+            Some(sector) => {
+                buffer.clone_from_slice(sector);
+                Ok(())
+            }
+            None => match &self.uninit {
+                UninitPolicy::Error => Err(ReadError::Uninitialized {
+                    offset: sector_idx * S::to_usize(),
+                }),
+                UninitPolicy::Zero => {
+                    let zeros = vec![0; W::NUM_BYTES];
+                    let (zero, _) = W::from(&zeros).ok_or(ReadError::InvalidData {
+                        offset: sector_idx * S::to_usize(),
+                    })?;
+
+                    for word in buffer.iter_mut() {
+                        *word = zero.clone();
+                    }
+                    Ok(())
+                }
+                UninitPolicy::EraseValue(erased) => {
+                    for word in buffer.iter_mut() {
+                        *word = erased.clone();
+                    }
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn write_sector(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<W, S>,
+    ) -> Result<(), WriteError<Infallible>> {
+        if sector_idx >= self.size_in_sectors {
+            return Err(WriteError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.size_in_sectors,
+            });
+        }
+
+        let _ = self.sectors.insert(sector_idx, words.clone());
+        Ok(())
+    }
+}
+
+/// Only the sectors that have been written to are stored, so
+/// [`tracked_usage`](InitTracking::tracked_usage) doesn't read anything:
+///
+/// ```rust
+/// # use storage_traits::{InitTracking, SparseStorage, Storage, Usage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = SparseStorage::<u8, U4>::new(10);
+/// for idx in [0, 3, 4, 9].iter() {
+///     storage.write_sector(*idx, &GenericArray::from([0xAB; 4])).unwrap();
+/// }
+///
+/// assert_eq!(
+///     storage.tracked_usage(),
+///     Usage { total_sectors: 10, erased_sectors: 6, written_sectors: 4 },
+/// );
+/// ```
+impl<W: AsBytes + Clone, S: ArrayLength<W>> InitTracking for SparseStorage<W, S> {
+    fn next_initialized_sector(&self, sector_idx: usize) -> Option<usize> {
+        self.sectors.range(sector_idx..).next().map(|(idx, _)| *idx)
+    }
+}
+
+impl<W: AsBytes + Clone, S: ArrayLength<W>> PhysicalMapping for SparseStorage<W, S> {
+    fn physical_sector(&self, logical: usize) -> Option<usize> {
+        crate::extensions::identity_mapping(self, logical)
+    }
+}
+
+/// Snapshots are clones of the whole storage medium (only the written sectors
+/// are copied); restoring reuses the existing allocations where it can.
+///
+/// ```rust
+/// # use storage_traits::{Snapshottable, SparseStorage, Storage};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = SparseStorage::<u8, U4>::new(4);
+/// storage.write_sector(0, &GenericArray::from([1; 4])).unwrap();
+///
+/// let before = storage.snapshot();
+/// storage.write_sector(0, &GenericArray::from([2; 4])).unwrap();
+/// storage.write_sector(2, &GenericArray::from([3; 4])).unwrap();
+///
+/// storage.restore_snapshot(&before);
+///
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(0, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([1; 4]));
+/// assert!(storage.read_sector(2, &mut buf).is_err());
+/// ```
+impl<W: AsBytes + Clone, S: ArrayLength<W>> Snapshottable for SparseStorage<W, S> {
+    type Snapshot = Self;
+
+    fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &Self) {
+        self.clone_from(snapshot);
+    }
+}
+
+/// Trimmed sectors are dropped from the map, so they go back to being read
+/// according to the [`UninitPolicy`].
+///
+/// ```rust
+/// # use storage_traits::{SparseStorage, Storage, Trimmable, UninitPolicy};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// let mut storage = SparseStorage::<u8, U4>::new(8).with_uninit_policy(UninitPolicy::EraseValue(0xFF));
+/// for idx in 0..4 {
+///     storage.write_sector(idx, &GenericArray::from([idx as u8; 4])).unwrap();
+/// }
+///
+/// storage.trim(1, 2).unwrap();
+///
+/// let mut buf = GenericArray::default();
+/// storage.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([0xFF; 4]));
+/// storage.read_sector(3, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([3; 4]));
+///
+/// assert!(storage.trim(7, 2).is_err());
+/// ```
+impl<W: AsBytes + Clone, S: ArrayLength<W>> Trimmable for SparseStorage<W, S> {
+    fn trim(&mut self, start_sector: usize, len: usize) -> Result<(), WriteError<Infallible>> {
+        match start_sector.checked_add(len) {
+            Some(end) if end <= self.size_in_sectors => {
+                let trimmed: Vec<usize> = self.sectors.range(start_sector..end).map(|(idx, _)| *idx).collect();
+                for idx in trimmed {
+                    let _ = self.sectors.remove(&idx);
+                }
+
+                Ok(())
+            }
+            _ => Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(start_sector, len),
+                max_offset: self.size_in_sectors,
+            }),
+        }
+    }
+}
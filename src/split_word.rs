@@ -0,0 +1,183 @@
+//! Home of the [`SplitWord`](SplitWord) composite.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, Storage};
+use super::errors::{ReadError, SplitReadError};
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// A composite of two physically adjacent storage mediums with different word
+/// types (i.e. a `u32` word metadata region followed by a `u8` word data
+/// region), which can't be expressed as one [`Storage`] since `Word` is fixed.
+///
+/// Both regions must have the same sector size in bytes (see
+/// [`Storage::sector_size_in_bytes`]).
+///
+/// ## Byte level addressing
+///
+/// The unified view ([`get`](SplitWord::get)) is byte addressed: bytes
+/// [0, [`meta_len`](SplitWord::meta_len)) are the metadata region's and the
+/// data region's bytes follow, up to [`len`](SplitWord::len). Each word is
+/// laid out as its bytes (see [`AsBytes::to`]), in order.
+///
+/// The typed accessors ([`read_meta_word`](SplitWord::read_meta_word) and
+/// [`read_data_word`](SplitWord::read_data_word)) take word offsets that are
+/// relative to the start of their region.
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, SplitWord};
+/// # use typenum::consts::{U4, U16};
+/// let mut meta = [0u32; 4 * 2];
+/// let mut data = [0u8; 16 * 3];
+/// meta[5] = 0xC0FF_EE00;
+/// data[7] = 0x42;
+///
+/// let mut split = SplitWord::new(
+///     SliceStorage::<u32, U4>::new(&mut meta),
+///     SliceStorage::<u8, U16>::new(&mut data),
+/// ).unwrap();
+/// assert_eq!((split.meta_len(), split.len()), (32, 80));
+///
+/// assert_eq!(split.read_meta_word(5), Ok(0xC0FF_EE00));
+/// assert_eq!(split.read_data_word(7), Ok(0x42));
+///
+/// // The same words through the byte addressed view:
+/// assert_eq!(split.get(5 * 4), Ok(0x00));
+/// assert_eq!(split.get(5 * 4 + 3), Ok(0xC0));
+/// assert_eq!(split.get(32 + 7), Ok(0x42));
+/// assert!(split.get(80).is_err());
+/// ```
+///
+/// Regions with different sector sizes can't be combined:
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, SplitWord};
+/// # use typenum::consts::U4;
+/// let mut meta = [0u32; 4];
+/// let mut data = [0u8; 4];
+/// assert!(SplitWord::new(SliceStorage::<u32, U4>::new(&mut meta), SliceStorage::<u8, U4>::new(&mut data)).is_none());
+/// ```
+#[derive(Debug)]
+pub struct SplitWord<M: Storage, D: Storage> {
+    meta: M,
+    data: D,
+}
+
+impl<M: Storage, D: Storage> SplitWord<M, D> {
+    /// Combines `meta` (which comes first) and `data`.
+    ///
+    /// Returns `None` if their sector sizes (in bytes) differ.
+    pub fn new(meta: M, data: D) -> Option<Self> {
+        if M::sector_size_in_bytes() != D::sector_size_in_bytes() {
+            return None;
+        }
+
+        Some(Self { meta, data })
+    }
+
+    /// Unwraps this composite, returning the two regions.
+    pub fn into_inner(self) -> (M, D) {
+        (self.meta, self.data)
+    }
+
+    /// Borrows the metadata region.
+    pub fn meta(&self) -> &M {
+        &self.meta
+    }
+
+    /// Mutably borrows the metadata region.
+    pub fn meta_mut(&mut self) -> &mut M {
+        &mut self.meta
+    }
+
+    /// Borrows the data region.
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    /// Mutably borrows the data region.
+    pub fn data_mut(&mut self) -> &mut D {
+        &mut self.data
+    }
+
+    /// The number of bytes in the metadata region; this is also the byte offset
+    /// that the data region starts at.
+    pub fn meta_len(&self) -> usize {
+        self.meta.capacity_in_bytes()
+    }
+
+    /// The number of bytes in both regions.
+    pub fn len(&self) -> usize {
+        self.meta_len() + self.data.capacity_in_bytes()
+    }
+
+    /// Whether both regions are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the word at `word_offset` (relative to the start of the metadata
+    /// region).
+    pub fn read_meta_word(&mut self, word_offset: usize) -> Result<M::Word, ReadError<M::ReadErr>>
+    where
+        M::Word: Default + Clone,
+    {
+        read_word(&mut self.meta, word_offset)
+    }
+
+    /// Reads the word at `word_offset` (relative to the start of the data
+    /// region).
+    pub fn read_data_word(&mut self, word_offset: usize) -> Result<D::Word, ReadError<D::ReadErr>>
+    where
+        D::Word: Default + Clone,
+    {
+        read_word(&mut self.data, word_offset)
+    }
+
+    /// Reads the byte at `byte_offset` in the byte addressed view (see the
+    /// [type level docs](SplitWord)).
+    pub fn get(&mut self, byte_offset: usize) -> Result<u8, SplitReadError<M::ReadErr, D::ReadErr>>
+    where
+        M::Word: Default + Clone,
+        D::Word: Default + Clone,
+    {
+        if byte_offset >= self.len() {
+            return Err(SplitReadError::OutOfRange {
+                requested_offset: byte_offset,
+                max_offset: self.len(),
+            });
+        }
+
+        if byte_offset < self.meta_len() {
+            let word = self.read_meta_word(byte_offset / M::Word::NUM_BYTES)
+                .map_err(SplitReadError::Meta)?;
+
+            Ok(word.to().as_ref()[byte_offset % M::Word::NUM_BYTES])
+        } else {
+            let byte_offset = byte_offset - self.meta_len();
+            let word = self.read_data_word(byte_offset / D::Word::NUM_BYTES)
+                .map_err(SplitReadError::Data)?;
+
+            Ok(word.to().as_ref()[byte_offset % D::Word::NUM_BYTES])
+        }
+    }
+}
+
+fn read_word<S: Storage>(storage: &mut S, word_offset: usize) -> Result<S::Word, ReadError<S::ReadErr>>
+where
+    S::Word: Default + Clone,
+{
+    if word_offset >= storage.capacity_in_words() {
+        return Err(ReadError::OutOfRange {
+            requested_offset: word_offset,
+            max_offset: storage.capacity_in_words(),
+        });
+    }
+
+    let mut sector = GenericArray::default();
+    storage.read_sector(word_offset / S::SECTOR_SIZE::to_usize(), &mut sector)?;
+
+    Ok(sector[word_offset % S::SECTOR_SIZE::to_usize()].clone())
+}
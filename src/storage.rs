@@ -1,12 +1,15 @@
 //! Holds the core [`Storage`](Storage) trait.
 
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
 use super::AsBytes;
-use super::errors::{ReadError, WriteError};
+use super::errors::{PartialReadError, ReadError, UpdateError, WriteError};
 
 use core::fmt::Debug;
 
 use generic_array::{GenericArray, ArrayLength};
 use typenum::marker_traits::Unsigned;
+use typenum::{IsLessOrEqual, True};
 
 /// The core [`Storage`] trait. Offers sector based writes and word based reads.
 ///
@@ -27,6 +30,33 @@ use typenum::marker_traits::Unsigned;
 /// For such storage mediums and use cases, we offer the _[extensions]_, traits
 /// that extend the core [`Storage`] trait with additional functionality.
 ///
+/// ## Trait objects
+///
+/// [`Storage`] can be used as a trait object (with all of its associated types
+/// specified) and is implemented for `&mut S` and (when `no_std` is not
+/// enabled) `Box<S>` where `S` is any [`Storage`], trait objects included. This
+/// means adapters can wrap storage mediums that are only known at runtime:
+///
+/// ```rust
+/// # use storage_traits::{SliceStorage, Storage, adapters::Partition};
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U4;
+/// # use std::convert::Infallible;
+/// type DynStorage<'a> = dyn Storage<Word = u8, SECTOR_SIZE = U4, ReadErr = Infallible, WriteErr = Infallible> + 'a;
+///
+/// let mut words = [0u8; 4 * 6];
+/// for (idx, w) in words.iter_mut().enumerate() { *w = idx as u8; }
+///
+/// let mut backend = SliceStorage::<u8, U4>::new(&mut words);
+/// let backend: &mut DynStorage<'_> = &mut backend;
+/// let mut part = Partition::new(backend, 2, 3).unwrap();
+///
+/// let mut buf = GenericArray::default();
+/// part.read_sector(1, &mut buf).unwrap();
+/// assert_eq!(buf, GenericArray::from([12, 13, 14, 15]));
+/// assert!(part.read_sector(3, &mut buf).is_err());
+/// ```
+///
 /// [`Storage`]: Storage
 /// [extensions]: super::extensions
 pub trait Storage {
@@ -60,14 +90,154 @@ pub trait Storage {
     /// In units of sectors.
     fn capacity(&self) -> usize;
 
+    /// The size of a sector in bytes.
+    ///
+    /// Panics if this doesn't fit in a `usize`.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage};
+    /// # use typenum::consts::U512;
+    /// # use typenum::marker_traits::Unsigned;
+    /// assert_eq!(SliceStorage::<u32, U512>::sector_size_in_bytes(), U512::to_usize() * 4);
+    /// assert_eq!(SliceStorage::<u32, U512>::sector_size_in_bytes(), 2048);
+    /// ```
+    fn sector_size_in_bytes() -> usize
+    where
+        Self: Sized,
+    {
+        sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>()
+    }
+
+    /// Rounds `byte_offset` down to the start of the sector it's in.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage};
+    /// # use typenum::consts::U256;
+    /// let mut words = [0u16; 256 * 4];
+    /// let storage = SliceStorage::<u16, U256>::new(&mut words); // 512 byte sectors
+    ///
+    /// assert_eq!(storage.align_down_to_sector(0), 0);
+    /// assert_eq!(storage.align_down_to_sector(511), 0);
+    /// assert_eq!(storage.align_down_to_sector(512), 512);
+    /// assert_eq!(storage.align_down_to_sector(1000), 512);
+    ///
+    /// assert_eq!(storage.align_up_to_sector(0), Some(0));
+    /// assert_eq!(storage.align_up_to_sector(1), Some(512));
+    /// assert_eq!(storage.align_up_to_sector(512), Some(512));
+    /// assert_eq!(storage.align_up_to_sector(513), Some(1024));
+    /// assert_eq!(storage.align_up_to_sector(usize::MAX), None);
+    ///
+    /// assert!(storage.is_sector_aligned(0));
+    /// assert!(storage.is_sector_aligned(1024));
+    /// assert!(!storage.is_sector_aligned(1023));
+    /// ```
+    fn align_down_to_sector(&self, byte_offset: usize) -> usize {
+        byte_offset - (byte_offset % sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>())
+    }
+
+    /// Rounds `byte_offset` up to the start of the next sector (if it isn't
+    /// already at the start of a sector); i.e. to round a length up to a whole
+    /// number of sectors.
+    ///
+    /// Returns `None` if the result doesn't fit in a `usize`. See
+    /// [`align_down_to_sector`](Storage::align_down_to_sector).
+    fn align_up_to_sector(&self, byte_offset: usize) -> Option<usize> {
+        let sector_size_in_bytes = sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>();
+
+        match byte_offset % sector_size_in_bytes {
+            0 => Some(byte_offset),
+            rem => byte_offset.checked_add(sector_size_in_bytes - rem),
+        }
+    }
+
+    /// Whether `byte_offset` is at the start of a sector. See
+    /// [`align_down_to_sector`](Storage::align_down_to_sector).
+    fn is_sector_aligned(&self, byte_offset: usize) -> bool {
+        byte_offset.is_multiple_of(sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>())
+    }
+
     /// In units of words.
+    ///
+    /// Saturates at `usize::MAX` for storage mediums with more words than a
+    /// `usize` can count (i.e. large images on 32-bit hosts).
     fn capacity_in_words(&self) -> usize {
-        self.capacity() * Self::SECTOR_SIZE::to_usize()
+        self.capacity().saturating_mul(Self::SECTOR_SIZE::to_usize())
     }
 
     /// In units of bytes.
+    ///
+    /// Saturates at `usize::MAX`, like
+    /// [`capacity_in_words`](Storage::capacity_in_words).
     fn capacity_in_bytes(&self) -> usize {
-        self.capacity_in_words() * <Self::Word as AsBytes>::NUM_BYTES
+        self.capacity_in_words().saturating_mul(<Self::Word as AsBytes>::NUM_BYTES)
+    }
+
+    /// The smallest unit that can be written at once, in units of words.
+    ///
+    /// This is the sector size unless the storage medium supports sub-sector
+    /// page writes (see [`PageWritable`](crate::PageWritable)) in which case
+    /// implementors should override this to return the page size.
+    fn write_page_size(&self) -> usize {
+        Self::SECTOR_SIZE::to_usize()
+    }
+
+    /// A hint for how many sectors bulk operations should move at a time for
+    /// the best throughput on this storage medium (i.e. large for files, the
+    /// whole device for memory mapped media).
+    ///
+    /// This is purely advisory; the default is `1`. The scanning helpers in
+    /// [`StorageExt`](crate::StorageExt) (i.e.
+    /// [`find_word`](crate::StorageExt::find_word) and
+    /// [`erase_map`](crate::StorageExt::erase_map)) read this many sectors at a
+    /// time with [`read_sectors`](Storage::read_sectors) when `std` is
+    /// available.
+    fn optimal_chunk_sectors(&self) -> usize {
+        1
+    }
+
+    /// The absolute byte offset of the sector at `sector_idx` within the
+    /// backing store (i.e. a file), for correlating with external tools like
+    /// `dd` or hex editors.
+    ///
+    /// Returns `None` for storage mediums without a concrete byte layout (the
+    /// default) and for sectors that are out of range.
+    fn sector_byte_offset(&self, _sector_idx: usize) -> Option<u64> {
+        None
+    }
+
+    /// A snapshot of the geometry of the storage medium (all of the above, in
+    /// one place).
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct Mem([[u16; 4]; 8]);
+    /// # impl Storage for Mem {
+    /// #     type Word = u16; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { self.0.len() }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u16, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u16, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// let storage = Mem::default();
+    /// let geometry = storage.geometry();
+    ///
+    /// assert_eq!(geometry.sectors, storage.capacity());
+    /// assert_eq!(geometry.capacity_in_words(), Some(storage.capacity_in_words()));
+    /// assert_eq!(geometry.capacity_in_bytes(), Some(storage.capacity_in_bytes()));
+    /// assert_eq!(geometry.sector_size_in_bytes(), Some(8));
+    /// ```
+    fn geometry(&self) -> Geometry {
+        Geometry {
+            sectors: self.capacity(),
+            words_per_sector: Self::SECTOR_SIZE::to_usize(),
+            bytes_per_word: <Self::Word as AsBytes>::NUM_BYTES,
+        }
     }
 
     /// Reads in an entire sector.
@@ -104,10 +274,245 @@ pub trait Storage {
         words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
     ) -> Result<(), WriteError<Self::WriteErr>>;
 
-    // TODO!
-    // fn write_sectors(&mut self, starting_sector_idx: usize, buffer: &mut [Self::Word])
-    // takes a flat array of bytes; it's length must be a multiple of the sector
-    // size in words.
+    /// Like [`read_sector`](Storage::read_sector) but implementations may skip
+    /// checking that `sector_idx` is in range (requires the `unchecked`
+    /// feature).
+    ///
+    /// This is for hot loops that have already validated their indices (i.e.
+    /// a scan over `0..self.capacity()`). The default implementation just calls
+    /// [`read_sector`](Storage::read_sector); in-memory backends override it.
+    ///
+    /// # Safety
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`). Calling this with an
+    /// index that's out of range is undefined behavior (implementations are
+    /// allowed to read out of bounds); it is _not_ guaranteed to return
+    /// [`ReadError::OutOfRange`].
+    #[cfg(feature = "unchecked")]
+    #[allow(unsafe_code)]
+    unsafe fn read_sector_unchecked(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        self.read_sector(sector_idx, buffer)
+    }
+
+    /// Like [`write_sector`](Storage::write_sector) but implementations may
+    /// skip checking that `sector_idx` is in range (requires the `unchecked`
+    /// feature). See [`read_sector_unchecked`](Storage::read_sector_unchecked).
+    ///
+    /// # Safety
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`). Calling this with an
+    /// index that's out of range is undefined behavior (implementations are
+    /// allowed to write out of bounds).
+    #[cfg(feature = "unchecked")]
+    #[allow(unsafe_code)]
+    unsafe fn write_sector_unchecked(
+        &mut self,
+        sector_idx: usize,
+        words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        self.write_sector(sector_idx, words)
+    }
+
+    /// Writes out a contiguous run of whole sectors, starting at
+    /// `starting_sector_idx`.
+    ///
+    /// The entire range must be in [0, `self.capacity()`) for this to succeed;
+    /// this is checked _before_ anything is written.
+    ///
+    /// This function has a naïve default implementation that calls
+    /// [`write_sector`](Storage::write_sector) for each sector; implementors
+    /// that can batch writes should override this.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct Mem([[u8; 4]; 8]);
+    /// # impl Storage for Mem {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { self.0.len() }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// let mut storage = Mem::default();
+    /// let data = [
+    ///     GenericArray::from([1, 1, 1, 1]),
+    ///     GenericArray::from([2, 2, 2, 2]),
+    ///     GenericArray::from([3, 3, 3, 3]),
+    /// ];
+    ///
+    /// storage.write_sectors(2, &data).unwrap();
+    /// assert_eq!(storage.0[1..6], [[0; 4], [1; 4], [2; 4], [3; 4], [0; 4]]);
+    ///
+    /// // Runs that don't fit are rejected without writing anything:
+    /// assert!(storage.write_sectors(6, &data).is_err());
+    /// assert_eq!(storage.0[6..], [[0; 4], [0; 4]]);
+    ///
+    /// // Runs that end exactly at the capacity fit; empty runs may start there:
+    /// storage.write_sectors(5, &data).unwrap();
+    /// storage.write_sectors(8, &[]).unwrap();
+    /// assert_eq!(
+    ///     storage.write_sectors(9, &[]),
+    ///     Err(WriteError::OutOfRange { requested_offset: 9, max_offset: 8 }),
+    /// );
+    /// assert_eq!(
+    ///     storage.write_sectors(7, &data),
+    ///     Err(WriteError::OutOfRange { requested_offset: 9, max_offset: 8 }),
+    /// );
+    /// ```
+    fn write_sectors(
+        &mut self,
+        starting_sector_idx: usize,
+        sectors: &[GenericArray<Self::Word, Self::SECTOR_SIZE>],
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        match starting_sector_idx.checked_add(sectors.len()) {
+            Some(end) if end <= self.capacity() => {},
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(starting_sector_idx, sectors.len()),
+                max_offset: self.capacity(),
+            }),
+        }
+
+        for (idx, sector) in sectors.iter().enumerate() {
+            self.write_sector(starting_sector_idx + idx, sector)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a contiguous run of whole sectors, starting at
+    /// `starting_sector_idx`, into `sectors`.
+    ///
+    /// The entire range must be in [0, `self.capacity()`) for this to succeed;
+    /// this is checked _before_ anything is read.
+    ///
+    /// Like [`write_sectors`](Storage::write_sectors), the default
+    /// implementation calls [`read_sector`](Storage::read_sector) for each
+    /// sector; implementors that can batch reads should override this (and
+    /// [`optimal_chunk_sectors`](Storage::optimal_chunk_sectors)).
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage, errors::ReadError};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U2;
+    /// let mut words = [0u8, 0, 1, 1, 2, 2, 3, 3];
+    /// let mut storage = SliceStorage::<u8, U2>::new(&mut words);
+    ///
+    /// let mut sectors = [GenericArray::default(); 3];
+    /// storage.read_sectors(1, &mut sectors).unwrap();
+    /// assert_eq!(sectors, [GenericArray::from([1, 1]), GenericArray::from([2, 2]), GenericArray::from([3, 3])]);
+    ///
+    /// assert_eq!(
+    ///     storage.read_sectors(2, &mut sectors),
+    ///     Err(ReadError::OutOfRange { requested_offset: 4, max_offset: 4 }),
+    /// );
+    /// ```
+    fn read_sectors(
+        &mut self,
+        starting_sector_idx: usize,
+        sectors: &mut [GenericArray<Self::Word, Self::SECTOR_SIZE>],
+    ) -> Result<(), ReadError<Self::ReadErr>> {
+        match starting_sector_idx.checked_add(sectors.len()) {
+            Some(end) if end <= self.capacity() => {},
+            _ => return Err(ReadError::OutOfRange {
+                requested_offset: crate::errors::last_offset(starting_sector_idx, sectors.len()),
+                max_offset: self.capacity(),
+            }),
+        }
+
+        for (idx, sector) in sectors.iter_mut().enumerate() {
+            self.read_sector(starting_sector_idx + idx, sector)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads in a sector, hands it to `func` to modify, and then writes it back
+    /// out.
+    ///
+    /// `sector_idx` must be in [0, `self.capacity()`) for this to succeed.
+    ///
+    /// If the read fails, `func` isn't called and nothing is written.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct Mem([[u8; 4]; 8]);
+    /// # impl Storage for Mem {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { self.0.len() }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// let mut storage = Mem::default();
+    /// storage.0[3] = [41, 1, 2, 3];
+    ///
+    /// storage.update_sector(3, |sector| sector[0] += 1).unwrap();
+    /// assert_eq!(storage.0[3], [42, 1, 2, 3]);
+    /// ```
+    ///
+    /// Errors say which phase they came from:
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, errors::{ReadError, UpdateError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct ReadOnly;
+    /// # impl Storage for ReadOnly {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = &'static str;
+    /// #     fn capacity(&self) -> usize { 2 }
+    /// #     fn read_sector(&mut self, idx: usize, _: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         if idx < 2 { Ok(()) } else { Err(ReadError::OutOfRange { requested_offset: idx, max_offset: 2 }) }
+    /// #     }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<&'static str>> {
+    /// #         Err(WriteError::Other("read only"))
+    /// #     }
+    /// # }
+    /// let mut storage = ReadOnly;
+    ///
+    /// assert_eq!(
+    ///     storage.update_sector(5, |_| {}),
+    ///     Err(UpdateError::Read(ReadError::OutOfRange { requested_offset: 5, max_offset: 2 })),
+    /// );
+    /// assert_eq!(
+    ///     storage.update_sector(1, |_| {}),
+    ///     Err(UpdateError::Write(WriteError::Other("read only"))),
+    /// );
+    /// ```
+    fn update_sector<F>(
+        &mut self,
+        sector_idx: usize,
+        func: F,
+    ) -> Result<(), UpdateError<Self::ReadErr, Self::WriteErr>>
+    where
+        Self: Sized,
+        F: FnOnce(&mut GenericArray<Self::Word, Self::SECTOR_SIZE>),
+        Self::Word: Default,
+    {
+        let mut sector = GenericArray::default();
+
+        self.read_sector(sector_idx, &mut sector)?;
+        func(&mut sector);
+        self.write_sector(sector_idx, &sector)?;
+
+        Ok(())
+    }
 
     // TODO!
     // provide a default impl that does error checking and then calls
@@ -121,14 +526,43 @@ pub trait Storage {
     //     bytes: &[u8]
     // ) -> Result<&[u8], WriteError<Self::WriteErr>>;
 
-    // TODO!
-    // have write_sector have a default impl that calls this.
-    // this should do error checking and what not (i.e. add the standard docs)
-    // fn write_sector_with_words(
-    //     &mut self,
-    //     sector_idx: usize,
-    //     sectors: &[Self::Word]
-    // ) -> Result<(), WriteError<Self::WriteErr>>;
+    /// [`write_sector`](Storage::write_sector), for words that come in a
+    /// slice (i.e. from a dynamically sized source) rather than a
+    /// [`GenericArray`].
+    ///
+    /// `words` must be exactly one sector long; other lengths fail with
+    /// [`WriteError::InvalidNumberOfWords`] before anything is written, rather
+    /// than panicking in the conversion to a [`GenericArray`].
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage, errors::WriteError};
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u16; 4 * 2];
+    /// let mut storage = SliceStorage::<u16, U4>::new(&mut words);
+    ///
+    /// storage.write_sector_with_words(1, &[1, 2, 3, 4]).unwrap();
+    /// assert_eq!(
+    ///     storage.write_sector_with_words(0, &[1, 2, 3]),
+    ///     Err(WriteError::InvalidNumberOfWords { words_given: 3, words_in_a_sector: 4 }),
+    /// );
+    ///
+    /// drop(storage);
+    /// assert_eq!(words, [0, 0, 0, 0, 1, 2, 3, 4]);
+    /// ```
+    fn write_sector_with_words(
+        &mut self,
+        sector_idx: usize,
+        words: &[Self::Word],
+    ) -> Result<(), WriteError<Self::WriteErr>> {
+        if words.len() != Self::SECTOR_SIZE::to_usize() {
+            return Err(WriteError::InvalidNumberOfWords {
+                words_given: words.len(),
+                words_in_a_sector: Self::SECTOR_SIZE::to_usize(),
+            });
+        }
+
+        self.write_sector(sector_idx, GenericArray::from_slice(words))
+    }
 
     // TODO!
     // type EraseErr;
@@ -139,6 +573,166 @@ pub trait Storage {
     // stack space (1 whole sector's worth).
 }
 
+/// [`Storage::sector_size_in_bytes`], for provided methods that have to work
+/// when `Self` isn't `Sized`.
+pub(crate) fn sector_size_in_bytes<W: AsBytes, N: Unsigned>() -> usize {
+    N::to_usize()
+        .checked_mul(W::NUM_BYTES)
+        .expect("the size of a sector in bytes overflows a `usize`")
+}
+
+macro_rules! forward_storage {
+    ($($(#[$attr:meta])* $ptr:ty),* $(,)?) => {$(
+        $(#[$attr])*
+        impl<S: Storage + ?Sized> Storage for $ptr {
+            type Word = S::Word;
+            type SECTOR_SIZE = S::SECTOR_SIZE;
+
+            type ReadErr = S::ReadErr;
+            type WriteErr = S::WriteErr;
+
+            fn capacity(&self) -> usize {
+                (**self).capacity()
+            }
+
+            fn write_page_size(&self) -> usize {
+                (**self).write_page_size()
+            }
+
+            fn optimal_chunk_sectors(&self) -> usize {
+                (**self).optimal_chunk_sectors()
+            }
+
+            fn sector_byte_offset(&self, sector_idx: usize) -> Option<u64> {
+                (**self).sector_byte_offset(sector_idx)
+            }
+
+            fn read_sector(
+                &mut self,
+                sector_idx: usize,
+                buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+            ) -> Result<(), ReadError<Self::ReadErr>> {
+                (**self).read_sector(sector_idx, buffer)
+            }
+
+            fn write_sector(
+                &mut self,
+                sector_idx: usize,
+                words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+            ) -> Result<(), WriteError<Self::WriteErr>> {
+                (**self).write_sector(sector_idx, words)
+            }
+
+            fn write_sectors(
+                &mut self,
+                starting_sector_idx: usize,
+                sectors: &[GenericArray<Self::Word, Self::SECTOR_SIZE>],
+            ) -> Result<(), WriteError<Self::WriteErr>> {
+                (**self).write_sectors(starting_sector_idx, sectors)
+            }
+
+            fn read_sectors(
+                &mut self,
+                starting_sector_idx: usize,
+                sectors: &mut [GenericArray<Self::Word, Self::SECTOR_SIZE>],
+            ) -> Result<(), ReadError<Self::ReadErr>> {
+                (**self).read_sectors(starting_sector_idx, sectors)
+            }
+
+            #[cfg(feature = "unchecked")]
+            #[allow(unsafe_code)]
+            unsafe fn read_sector_unchecked(
+                &mut self,
+                sector_idx: usize,
+                buffer: &mut GenericArray<Self::Word, Self::SECTOR_SIZE>,
+            ) -> Result<(), ReadError<Self::ReadErr>> {
+                // Safety: our caller upholds the same contract.
+                unsafe { (**self).read_sector_unchecked(sector_idx, buffer) }
+            }
+
+            #[cfg(feature = "unchecked")]
+            #[allow(unsafe_code)]
+            unsafe fn write_sector_unchecked(
+                &mut self,
+                sector_idx: usize,
+                words: &GenericArray<Self::Word, Self::SECTOR_SIZE>,
+            ) -> Result<(), WriteError<Self::WriteErr>> {
+                // Safety: our caller upholds the same contract.
+                unsafe { (**self).write_sector_unchecked(sector_idx, words) }
+            }
+        }
+    )*};
+}
+
+forward_storage!(&mut S);
+
+using_std! {
+    forward_storage!(
+        /// Boxed trait objects can be handed to adapters:
+        ///
+        /// ```rust
+        /// # use storage_traits::{RopeStorage, SparseStorage, Storage, adapters::Partition};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// # use std::convert::Infallible;
+        /// type DynStorage = dyn Storage<Word = u8, SECTOR_SIZE = U4, ReadErr = Infallible, WriteErr = Infallible>;
+        ///
+        /// fn open(sparse: bool) -> Box<DynStorage> {
+        ///     if sparse {
+        ///         Box::new(SparseStorage::<u8, U4>::new(8))
+        ///     } else {
+        ///         Box::new(RopeStorage::<u8, U4>::new(8))
+        ///     }
+        /// }
+        ///
+        /// for sparse in [true, false] {
+        ///     let mut part = Partition::new(open(sparse), 4, 2).unwrap();
+        ///     part.write_sector(1, &GenericArray::from([7; 4])).unwrap();
+        ///
+        ///     let mut buf = GenericArray::default();
+        ///     part.read_sector(1, &mut buf).unwrap();
+        ///     assert_eq!(buf, GenericArray::from([7; 4]));
+        ///
+        ///     let mut inner = part.into_inner();
+        ///     inner.read_sector(5, &mut buf).unwrap();
+        ///     assert_eq!(buf, GenericArray::from([7; 4]));
+        /// }
+        /// ```
+        Box<S>
+    );
+}
+
+/// The layout of a storage medium; see [`Storage::geometry`].
+///
+/// The derived quantities use checked arithmetic and return `None` on
+/// overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    /// Number of sectors.
+    pub sectors: usize,
+    /// Number of words in a sector.
+    pub words_per_sector: usize,
+    /// Number of bytes in a word.
+    pub bytes_per_word: usize,
+}
+
+impl Geometry {
+    /// In units of bytes.
+    pub fn sector_size_in_bytes(&self) -> Option<usize> {
+        self.words_per_sector.checked_mul(self.bytes_per_word)
+    }
+
+    /// In units of words.
+    pub fn capacity_in_words(&self) -> Option<usize> {
+        self.sectors.checked_mul(self.words_per_sector)
+    }
+
+    /// In units of bytes.
+    pub fn capacity_in_bytes(&self) -> Option<usize> {
+        self.capacity_in_words()?.checked_mul(self.bytes_per_word)
+    }
+}
+
 pub trait WordReadable: Storage {
     /// Implementations may return `ReadError::Uninitialized` for memory
     /// locations that have not been written to at their discretion.
@@ -147,10 +741,63 @@ pub trait WordReadable: Storage {
     fn read_word(&self, word_offset: usize)
         -> Result<Self::Word, ReadError<Self::ReadErr>>;
 
+    /// The alignment (in words) that multi-word reads need to have; both the
+    /// offset and the length of reads passed to
+    /// [`read_words`](WordReadable::read_words) must be multiples of this.
+    ///
+    /// Defaults to 1 (no alignment requirement). Backends with stricter
+    /// constraints (i.e. DMA engines that need aligned transfers) can override
+    /// this; generic callers can query it and pad their reads accordingly.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, WordReadable, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U8;
+    /// # #[derive(Debug, Default)]
+    /// # struct Dma([u8; 16]);
+    /// # impl Storage for Dma {
+    /// #     type Word = u8; type SECTOR_SIZE = U8; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 2 }
+    /// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U8>) -> Result<(), ReadError<()>> { unimplemented!() }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U8>) -> Result<(), WriteError<()>> { unimplemented!() }
+    /// # }
+    /// impl WordReadable for Dma {
+    ///     fn read_word(&self, offset: usize) -> Result<u8, ReadError<()>> { Ok(self.0[offset]) }
+    ///     fn read_alignment(&self) -> usize { 4 }
+    /// }
+    ///
+    /// let mut storage = Dma([3; 16]);
+    /// let mut buf = [0; 4];
+    ///
+    /// storage.read_words(4, &mut buf).unwrap();
+    /// assert_eq!(
+    ///     storage.read_words(1, &mut buf),
+    ///     Err(ReadError::Unaligned { offset: 1, alignment: 4 }),
+    /// );
+    /// assert_eq!(
+    ///     storage.read_words(4, &mut buf[..2]),
+    ///     Err(ReadError::Unaligned { offset: 4, alignment: 4 }),
+    /// );
+    /// assert_eq!(
+    ///     storage.read_words_partial(1, &mut buf).unwrap_err().error,
+    ///     ReadError::Unaligned { offset: 1, alignment: 4 },
+    /// );
+    /// ```
+    fn read_alignment(&self) -> usize {
+        1
+    }
+
     /// Reads in some chunk of data. There is no guarantee that the requested
     /// chunk is aligned to a sector or smaller than a sector.
     ///
-    /// `offset` must be in [0, `self.capacity_in_words()`) for this to succeed.
+    /// For backends with a [`read_alignment`](WordReadable::read_alignment)
+    /// larger than 1, reads whose offset or length isn't a multiple of the
+    /// alignment fail with [`ReadError::Unaligned`] (after the range check).
+    ///
+    /// `offset + buffer.len()` must not exceed `self.capacity_in_words()` for
+    /// this to succeed. Like with slices, zero-length reads always succeed (and
+    /// do nothing) for any `offset` in [0, `self.capacity_in_words()`],
+    /// including one-past-the-end.
     ///
     /// This function should never panic but can return errors for the
     /// appropriate cases (i.e. out of range).
@@ -162,18 +809,47 @@ pub trait WordReadable: Storage {
     /// This function has a naïve default implementation; implementors that can
     /// provide a more performant way to read in more than a word at a time
     /// should override this.
+    ///
+    /// The bounds match slice range semantics exactly:
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, WordReadable, errors::ReadError};
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u8; 8];
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+    ///
+    /// // An exclusive end equal to the capacity is fine...
+    /// storage.read_words(8, &mut []).unwrap();
+    /// storage.read_words(6, &mut [0; 2]).unwrap();
+    ///
+    /// // ...but nothing past it:
+    /// assert_eq!(
+    ///     storage.read_words(8, &mut [0; 1]),
+    ///     Err(ReadError::OutOfRange { requested_offset: 8, max_offset: 8 }),
+    /// );
+    /// assert_eq!(
+    ///     storage.read_words(9, &mut []),
+    ///     Err(ReadError::OutOfRange { requested_offset: 9, max_offset: 8 }),
+    /// );
+    /// assert!(storage.read_words(usize::MAX, &mut [0; 2]).is_err());
+    /// ```
     #[inline]
     fn read_words(
         &mut self,
         word_offset: usize,
         buffer: &mut [Self::Word],
     ) -> Result<(), ReadError<Self::ReadErr>> {
-        let max_offset = word_offset + (buffer.len() - 1);
-        if  max_offset >= self.capacity_in_words() {
-            return Err(ReadError::OutOfRange {
-                requested_offset: max_offset,
+        match word_offset.checked_add(buffer.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(ReadError::OutOfRange {
+                requested_offset: crate::errors::last_offset(word_offset, buffer.len()),
                 max_offset: self.capacity_in_words(),
-            });
+            }),
+        }
+
+        let alignment = self.read_alignment();
+        if alignment > 1 && !(word_offset.is_multiple_of(alignment) && buffer.len().is_multiple_of(alignment)) {
+            return Err(ReadError::Unaligned { offset: word_offset, alignment });
         }
 
         for (idx, word) in buffer.iter_mut().enumerate() {
@@ -182,4 +858,126 @@ pub trait WordReadable: Storage {
 
         Ok(())
     }
+
+    /// Like [`read_words`](WordReadable::read_words) but on failure reports
+    /// how much of `buffer` was filled in before the error happened (i.e. so
+    /// reads of flaky media can be resumed).
+    ///
+    /// On error, the first `words_read` words of `buffer` hold valid data and
+    /// the rest are unspecified. Range and alignment errors (see
+    /// [`read_words`](WordReadable::read_words)) are caught before anything is
+    /// read, so they always have `words_read` set to 0.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, WordReadable, errors::{PartialReadError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct Flaky([u8; 16]);
+    /// # impl Storage for Flaky {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = &'static str; type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 4 }
+    /// #     fn read_sector(&mut self, _: usize, _: &mut GenericArray<u8, U4>) -> Result<(), ReadError<&'static str>> { unimplemented!() }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> { unimplemented!() }
+    /// # }
+    /// // Word 10 can't be read:
+    /// impl WordReadable for Flaky {
+    ///     fn read_word(&self, offset: usize) -> Result<u8, ReadError<&'static str>> {
+    ///         if offset == 10 { Err(ReadError::Other("flaky")) } else { Ok(self.0[offset]) }
+    ///     }
+    /// }
+    ///
+    /// let mut storage = Flaky([7; 16]);
+    /// let mut buf = [0; 4];
+    ///
+    /// assert_eq!(
+    ///     storage.read_words_partial(8, &mut buf),
+    ///     Err(PartialReadError { words_read: 2, error: ReadError::Other("flaky") }),
+    /// );
+    /// assert_eq!(buf[..2], [7, 7]);
+    ///
+    /// assert_eq!(storage.read_words_partial(14, &mut buf).unwrap_err().words_read, 0);
+    /// ```
+    fn read_words_partial(
+        &mut self,
+        word_offset: usize,
+        buffer: &mut [Self::Word],
+    ) -> Result<(), PartialReadError<Self::ReadErr>> {
+        match word_offset.checked_add(buffer.len()) {
+            Some(end) if end <= self.capacity_in_words() => {},
+            _ => return Err(PartialReadError {
+                words_read: 0,
+                error: ReadError::OutOfRange {
+                    requested_offset: crate::errors::last_offset(word_offset, buffer.len()),
+                    max_offset: self.capacity_in_words(),
+                },
+            }),
+        }
+
+        let alignment = self.read_alignment();
+        if alignment > 1 && !(word_offset.is_multiple_of(alignment) && buffer.len().is_multiple_of(alignment)) {
+            return Err(PartialReadError {
+                words_read: 0,
+                error: ReadError::Unaligned { offset: word_offset, alignment },
+            });
+        }
+
+        for (idx, word) in buffer.iter_mut().enumerate() {
+            *word = self.read_word(word_offset + idx)
+                .map_err(|error| PartialReadError { words_read: idx, error })?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the first `K` words of the sector at `sector_idx` into `buffer`
+    /// (i.e. a header), without needing a buffer for the whole sector.
+    ///
+    /// `K` must not be larger than the sector size; this is checked at compile
+    /// time. `sector_idx` must be in [0, `self.capacity()`) for this to
+    /// succeed.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, WordReadable, errors::ReadError};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::{U8, U512};
+    /// let mut words = [0u8; 512 * 2];
+    /// for (idx, w) in words.iter_mut().enumerate() { *w = idx as u8; }
+    /// let mut storage = SliceStorage::<u8, U512>::new(&mut words);
+    ///
+    /// let mut header = GenericArray::<u8, U8>::default();
+    /// storage.read_partial_sector(1, &mut header).unwrap();
+    /// assert_eq!(header, GenericArray::from([0, 1, 2, 3, 4, 5, 6, 7]));
+    ///
+    /// assert_eq!(
+    ///     storage.read_partial_sector(2, &mut header),
+    ///     Err(ReadError::OutOfRange { requested_offset: 2, max_offset: 2 }),
+    /// );
+    /// ```
+    ///
+    /// ```rust,compile_fail
+    /// # use storage_traits::{SliceStorage, WordReadable};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::{U4, U5};
+    /// let mut words = [0u8; 8];
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+    /// storage.read_partial_sector(0, &mut GenericArray::<u8, U5>::default()).unwrap();
+    /// ```
+    fn read_partial_sector<K>(
+        &mut self,
+        sector_idx: usize,
+        buffer: &mut GenericArray<Self::Word, K>,
+    ) -> Result<(), ReadError<Self::ReadErr>>
+    where
+        K: ArrayLength<Self::Word> + IsLessOrEqual<Self::SECTOR_SIZE, Output = True>,
+    {
+        if sector_idx >= self.capacity() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: sector_idx,
+                max_offset: self.capacity(),
+            });
+        }
+
+        self.read_words(sector_idx * Self::SECTOR_SIZE::to_usize(), buffer)
+    }
 }
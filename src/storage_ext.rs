@@ -0,0 +1,1298 @@
+//! Home of the [`StorageExt`](StorageExt) trait.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use super::{AsBytes, Storage};
+
+use typenum::marker_traits::Unsigned;
+
+/// Types that occupy exactly one sector of a storage medium (i.e. a
+/// `#[repr(C)]` header struct).
+///
+/// Implementing this is a promise that [`AsBytes::NUM_BYTES`] is the size of a
+/// sector in bytes for the storages the type is used with; this is checked at
+/// compile time when the type is used with `StorageExt::read_typed` or
+/// `StorageExt::write_typed` (which require that `no_std` is not enabled).
+pub trait SectorType: AsBytes {}
+
+using_std! {
+    // Evaluating `FITS` fails to compile when the sizes don't match.
+    trait FitsInSector<S: Storage + ?Sized>: SectorType {
+        const FITS: ();
+    }
+
+    impl<S: Storage + ?Sized, T: SectorType> FitsInSector<S> for T {
+        const FITS: () = assert!(
+            T::NUM_BYTES == S::SECTOR_SIZE::USIZE * <S::Word as AsBytes>::NUM_BYTES,
+            "`SectorType` size does not match the storage's sector size",
+        );
+    }
+}
+
+type Sector<S> = generic_array::GenericArray<<S as Storage>::Word, <S as Storage>::SECTOR_SIZE>;
+
+// The buffer the scanning helpers read sectors into: `optimal_chunk_sectors`
+// sectors when we can allocate, one otherwise.
+#[cfg(feature = "no_std")]
+type ChunkBuffer<S> = [Sector<S>; 1];
+using_std! { type ChunkBuffer<S> = Vec<Sector<S>>; }
+
+#[cfg(feature = "no_std")]
+fn chunk_buffer<S: Storage + ?Sized>(_storage: &S) -> ChunkBuffer<S>
+where
+    S::Word: Default,
+{
+    [generic_array::GenericArray::default()]
+}
+
+using_std! {
+    fn chunk_buffer<S: Storage + ?Sized>(storage: &S) -> ChunkBuffer<S>
+    where
+        S::Word: Default,
+    {
+        (0..storage.optimal_chunk_sectors().max(1))
+            .map(|_| generic_array::GenericArray::default())
+            .collect()
+    }
+}
+
+/// Reads as many of the sectors starting at `start` as fit in `buf` (and in the
+/// storage medium) with one [`read_sectors`](Storage::read_sectors) call,
+/// returning how many were read. `start` must be in range.
+///
+/// If the batched read fails, only sector `start` is read (on its own) so that
+/// errors are reported for the sector that actually caused them.
+fn read_ahead<S: Storage + ?Sized>(
+    storage: &mut S,
+    start: usize,
+    buf: &mut [Sector<S>],
+) -> Result<usize, crate::errors::ReadError<S::ReadErr>> {
+    let count = buf.len().min(storage.capacity().saturating_sub(start));
+
+    if count > 1 && storage.read_sectors(start, &mut buf[..count]).is_ok() {
+        return Ok(count);
+    }
+
+    storage.read_sector(start, &mut buf[0])?;
+    Ok(1)
+}
+
+/// Reads the sector at `sector_idx` in for a read-modify-write, converting
+/// failures with [`read_error_into_write`](crate::errors::read_error_into_write).
+///
+/// Sectors that have never been written to come back as `Word::default()`s;
+/// returns whether the sector had been written to.
+pub(crate) fn read_for_write<S: Storage + ?Sized>(
+    storage: &mut S,
+    sector_idx: usize,
+    buffer: &mut Sector<S>,
+) -> Result<bool, crate::errors::WriteError<S::WriteErr>>
+where
+    S::Word: Default,
+{
+    match storage.read_sector(sector_idx, buffer) {
+        Ok(()) => Ok(true),
+        Err(err) => match crate::errors::read_error_into_write(err, sector_idx) {
+            Some(err) => Err(err),
+            None => {
+                *buffer = generic_array::GenericArray::default();
+                Ok(false)
+            }
+        },
+    }
+}
+
+/// Helpers for all [`Storage`] implementations.
+///
+/// This is implemented for every type that implements [`Storage`]; unlike the
+/// provided methods on [`Storage`] these aren't meant to be overridden.
+pub trait StorageExt: Storage {
+    /// Wraps this storage in a [`Clamped`](crate::adapters::Clamped) adapter.
+    fn clamped(self) -> crate::adapters::Clamped<Self>
+    where
+        Self: Sized,
+    {
+        crate::adapters::Clamped::new(self)
+    }
+
+    /// Wraps this storage in an [`AtomicSector`](crate::adapters::AtomicSector)
+    /// adapter.
+    ///
+    /// Adapters can be chained like iterator adapters:
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, Storage, StorageExt};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u8; 4 * 7];
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut words)
+    ///     .atomic_sectors()
+    ///     .unwrap()
+    ///     .clamped();
+    ///
+    /// assert_eq!(storage.capacity(), 3);
+    ///
+    /// let mut buf = GenericArray::default();
+    /// storage.write_sector(2, &GenericArray::from([1, 2, 3, 4])).unwrap();
+    /// storage.read_sector(2, &mut buf).unwrap();
+    /// assert_eq!(buf, GenericArray::from([1, 2, 3, 4]));
+    /// ```
+    fn atomic_sectors(self) -> Result<crate::adapters::AtomicSector<Self>, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self: Sized,
+        Self::Word: Default,
+    {
+        crate::adapters::AtomicSector::new(self)
+    }
+
+    /// Checks that each of the sectors in `test_sectors` can be written to and
+    /// read back from (i.e. as a bring-up test).
+    ///
+    /// For each sector, the original contents are read and saved, a pattern
+    /// with every bit of the sector flipped is written, read back and compared,
+    /// and then the original contents are written back. The original contents
+    /// are restored even if the pattern doesn't read back correctly.
+    ///
+    /// Stops at the first sector that fails. If both the verification and the
+    /// restore fail, the verification error is returned.
+    ///
+    /// The pattern is compared byte for byte (see [`AsBytes::to`]) rather than
+    /// with `Word`'s `PartialEq` impl (if any); this matches what's actually
+    /// stored and works for words like floats, where flipping every bit of a
+    /// zero gives a NaN that isn't equal to itself:
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, StorageExt};
+    /// # use typenum::consts::U4;
+    /// let mut words = [0.0f32, 1.5, f32::NAN, -0.0, f32::NAN, f32::NAN, f32::NAN, f32::NAN];
+    /// SliceStorage::<f32, U4>::new(&mut words).self_test(&[0, 1]).unwrap();
+    ///
+    /// assert_eq!(words[1], 1.5);
+    /// assert!(words[4..].iter().all(|w| w.is_nan()));
+    /// ```
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, StorageExt, errors::{ReadError, SelfTestError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug, Default)]
+    /// # struct Mem { sectors: [[u8; 4]; 4], stuck: Option<usize> }
+    /// # impl Storage for Mem {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { self.sectors.len() }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.sectors[idx]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.sectors[idx].copy_from_slice(words);
+    /// #         // Models a bit that's stuck at 0.
+    /// #         if self.stuck == Some(idx) { self.sectors[idx][0] &= !1; }
+    /// #         Ok(())
+    /// #     }
+    /// # }
+    /// let mut storage = Mem::default();
+    /// storage.sectors = [[1, 2, 3, 4], [5, 6, 7, 8], [8; 4], [0; 4]];
+    ///
+    /// storage.self_test(&[0, 1, 3]).unwrap();
+    /// assert_eq!(storage.sectors[1], [5, 6, 7, 8]);
+    ///
+    /// // Sector 2 has a stuck bit:
+    /// storage.stuck = Some(2);
+    /// assert_eq!(storage.self_test(&[1, 2, 3]), Err(SelfTestError::Mismatch { sector_idx: 2 }));
+    /// assert_eq!(storage.sectors[2], [8; 4]);
+    /// ```
+    fn self_test(
+        &mut self,
+        test_sectors: &[usize],
+    ) -> Result<(), crate::errors::SelfTestError<Self::ReadErr, Self::WriteErr>>
+    where
+        Self::Word: Default,
+    {
+        use crate::errors::{ReadError, SelfTestError};
+        use generic_array::GenericArray;
+
+        for &sector_idx in test_sectors {
+            let mut original = GenericArray::default();
+            self.read_sector(sector_idx, &mut original).map_err(SelfTestError::Read)?;
+
+            let mut pattern = GenericArray::default();
+            for (word, original) in pattern.iter_mut().zip(original.iter()) {
+                let mut bytes = original.to();
+                for byte in bytes.as_mut().iter_mut() {
+                    *byte = !*byte;
+                }
+
+                *word = AsBytes::from(bytes.as_ref())
+                    .map(|(word, _)| word)
+                    .ok_or(SelfTestError::Read(ReadError::InvalidData {
+                        offset: sector_idx * Self::SECTOR_SIZE::to_usize(),
+                    }))?;
+            }
+
+            let mut read_back = GenericArray::default();
+            let verified = self.write_sector(sector_idx, &pattern)
+                .map_err(SelfTestError::Write)
+                .and_then(|()| self.read_sector(sector_idx, &mut read_back).map_err(SelfTestError::Read))
+                .and_then(|()| if crate::bytes::bytes_eq(&read_back, &pattern) {
+                    Ok(())
+                } else {
+                    Err(SelfTestError::Mismatch { sector_idx })
+                });
+
+            let restored = self.write_sector(sector_idx, &original).map_err(SelfTestError::Write);
+
+            verified.and(restored)?;
+        }
+
+        Ok(())
+    }
+
+    /// Feeds the contents of the entire storage medium into `hasher`, one
+    /// sector at a time (i.e. to content-address an image without allocating).
+    ///
+    /// Each word is written to the hasher as its bytes (see [`AsBytes::to`]).
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, StorageExt};
+    /// # use std::collections::hash_map::DefaultHasher;
+    /// # use std::hash::Hasher;
+    /// # use typenum::consts::U4;
+    /// fn hash(words: &mut [u16]) -> u64 {
+    ///     let mut hasher = DefaultHasher::new();
+    ///     SliceStorage::<u16, U4>::new(words).hash_into(&mut hasher).unwrap();
+    ///     hasher.finish()
+    /// }
+    ///
+    /// let mut a = [7u16; 16];
+    /// let mut b = [7u16; 16];
+    /// assert_eq!(hash(&mut a), hash(&mut b));
+    ///
+    /// b[9] = 8;
+    /// assert_ne!(hash(&mut a), hash(&mut b));
+    /// ```
+    fn hash_into<H: core::hash::Hasher>(
+        &mut self,
+        hasher: &mut H,
+    ) -> Result<(), crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self::Word: Default,
+    {
+        let mut sector = generic_array::GenericArray::default();
+
+        for sector_idx in 0..self.capacity() {
+            self.read_sector(sector_idx, &mut sector)?;
+
+            for word in sector.iter() {
+                hasher.write(word.to().as_ref());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the offset of the first word at or after `start` (a word
+    /// offset) for which `pred` returns `true`, or `None` if no word up to the
+    /// end of the storage medium matches (i.e. to scan for a magic number).
+    ///
+    /// Words are read a sector at a time (or
+    /// [`optimal_chunk_sectors`](Storage::optimal_chunk_sectors) sectors at a
+    /// time, with `std`), not one by one. `start` can be
+    /// [`capacity_in_words`](Storage::capacity_in_words), in which case
+    /// nothing is read.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, StorageExt, errors::ReadError};
+    /// # use typenum::consts::U8;
+    /// let mut words = [0u32; 8 * 32];
+    /// words[100] = 0xFEED_FACE;
+    /// words[200] = 0xFEED_FACE;
+    /// let mut storage = SliceStorage::<u32, U8>::new(&mut words);
+    ///
+    /// let is_magic = |w: &u32| *w == 0xFEED_FACE;
+    /// assert_eq!(storage.find_word(0, is_magic), Ok(Some(100)));
+    /// assert_eq!(storage.find_word(100, is_magic), Ok(Some(100)));
+    /// assert_eq!(storage.find_word(101, is_magic), Ok(Some(200)));
+    /// assert_eq!(storage.find_word(201, is_magic), Ok(None));
+    /// assert_eq!(storage.find_word(256, is_magic), Ok(None));
+    ///
+    /// assert_eq!(
+    ///     storage.find_word(257, is_magic),
+    ///     Err(ReadError::OutOfRange { requested_offset: 257, max_offset: 256 }),
+    /// );
+    /// ```
+    fn find_word<P: Fn(&Self::Word) -> bool>(
+        &mut self,
+        start: usize,
+        pred: P,
+    ) -> Result<Option<usize>, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self::Word: Default,
+    {
+        if start > self.capacity_in_words() {
+            return Err(crate::errors::ReadError::OutOfRange {
+                requested_offset: start,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        let sector_size = Self::SECTOR_SIZE::to_usize();
+        let mut chunk = chunk_buffer(self);
+
+        let mut sector_idx = start / sector_size;
+        while sector_idx < self.capacity() {
+            let count = read_ahead(self, sector_idx, &mut chunk)?;
+
+            for sector in chunk[..count].iter() {
+                let base = sector_idx * sector_size;
+                let skip = start.saturating_sub(base);
+
+                if let Some(idx) = sector.iter().skip(skip).position(&pred) {
+                    return Ok(Some(base + skip + idx));
+                }
+
+                sector_idx += 1;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reverses the bytes of each of the `len` words starting at word `start`,
+    /// in place (i.e. to fix up data that was written with the wrong
+    /// endianness).
+    ///
+    /// Each word is turned into its bytes with [`AsBytes::to`], reversed, and
+    /// decoded again with [`AsBytes::from`]. This is only sensible for
+    /// integer-like words where byte reversal means something; words whose
+    /// reversed bytes don't decode are left as they were.
+    ///
+    /// This does one read-modify-write per sector touched; failed reads are
+    /// converted with [`read_error_into_write`](crate::errors::read_error_into_write)
+    /// and sectors that have never been written to are treated as holding
+    /// `Word::default()`s.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, StorageExt, WordReadable};
+    /// # use typenum::consts::U4;
+    /// let mut words = [0x1122_3344u32; 12];
+    /// let mut storage = SliceStorage::<u32, U4>::new(&mut words);
+    ///
+    /// storage.byteswap_words(2, 7).unwrap();
+    ///
+    /// let mut buf = [0; 12];
+    /// storage.read_words(0, &mut buf).unwrap();
+    /// assert_eq!(buf[..2], [0x1122_3344; 2]);
+    /// assert_eq!(buf[2..9], [u32::from_be_bytes(0x1122_3344u32.to_le_bytes()); 7]);
+    /// assert_eq!(buf[9..], [0x1122_3344; 3]);
+    ///
+    /// assert!(storage.byteswap_words(6, 7).is_err());
+    /// ```
+    fn byteswap_words(
+        &mut self,
+        start: usize,
+        len: usize,
+    ) -> Result<(), crate::errors::WriteError<Self::WriteErr>>
+    where
+        Self::Word: Default,
+    {
+        use crate::errors::WriteError;
+
+        let end = match start.checked_add(len) {
+            Some(end) if end <= self.capacity_in_words() => end,
+            _ => return Err(WriteError::OutOfRange {
+                requested_offset: crate::errors::last_offset(start, len),
+                max_offset: self.capacity_in_words(),
+            }),
+        };
+        if len == 0 {
+            return Ok(());
+        }
+
+        let sector_size = Self::SECTOR_SIZE::to_usize();
+        let mut sector: Sector<Self> = generic_array::GenericArray::default();
+
+        for sector_idx in (start / sector_size)..=((end - 1) / sector_size) {
+            let _ = read_for_write(self, sector_idx, &mut sector)?;
+
+            let base = sector_idx * sector_size;
+            let lo = start.saturating_sub(base);
+            let hi = (end - base).min(sector_size);
+
+            for word in sector[lo..hi].iter_mut() {
+                let mut bytes = word.to();
+                bytes.as_mut().reverse();
+
+                if let Some((swapped, _)) = AsBytes::from(bytes.as_ref()) {
+                    *word = swapped;
+                }
+            }
+
+            self.write_sector(sector_idx, &sector)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exchanges the contents of the sectors at `a` and `b`.
+    ///
+    /// Both sectors are read into buffers on the stack and then each is written
+    /// to the other's place. Both indices are checked before anything is read;
+    /// failed reads are converted with
+    /// [`read_error_into_write`](crate::errors::read_error_into_write) and
+    /// sectors that have never been written to are treated as holding
+    /// `Word::default()`s.
+    ///
+    /// This is _not_ atomic: if the second write fails (or power is lost
+    /// between the two writes) both sectors are left holding `b`'s old
+    /// contents. Note that this also needs two sectors' worth of stack space.
+    ///
+    /// ```rust
+    /// # use storage_traits::{SliceStorage, StorageExt};
+    /// # use typenum::consts::U4;
+    /// let mut words = [0u8; 4 * 6];
+    /// for (idx, w) in words.iter_mut().enumerate() { *w = (idx / 4) as u8; }
+    /// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+    ///
+    /// storage.swap_sectors(1, 4).unwrap();
+    /// assert!(storage.swap_sectors(1, 6).is_err());
+    ///
+    /// drop(storage);
+    /// assert_eq!(words[4..8], [4; 4]);
+    /// assert_eq!(words[16..20], [1; 4]);
+    /// assert_eq!(words[8..12], [2; 4]);
+    /// ```
+    fn swap_sectors(&mut self, a: usize, b: usize) -> Result<(), crate::errors::WriteError<Self::WriteErr>>
+    where
+        Self::Word: Default,
+    {
+        use crate::errors::WriteError;
+
+        let max = a.max(b);
+        if max >= self.capacity() {
+            return Err(WriteError::OutOfRange {
+                requested_offset: max,
+                max_offset: self.capacity(),
+            });
+        }
+        if a == b {
+            return Ok(());
+        }
+
+        let mut sector_a: Sector<Self> = generic_array::GenericArray::default();
+        let mut sector_b: Sector<Self> = generic_array::GenericArray::default();
+
+        let _ = read_for_write(self, a, &mut sector_a)?;
+        let _ = read_for_write(self, b, &mut sector_b)?;
+
+        self.write_sector(a, &sector_b)?;
+        self.write_sector(b, &sector_a)
+    }
+
+    /// Works out the byte order of the data in a (foreign) image by reading the
+    /// word at `word_offset`, which should hold a known magic value, and
+    /// checking which interpretation of its bytes matches.
+    ///
+    /// The word's bytes (see [`AsBytes::to`]) are read as a big endian integer
+    /// and compared against `expected_be` and then as a little endian integer
+    /// and compared against `expected_le`; usually both are the magic value.
+    /// If both match (i.e. for palindromic magic values), big endian wins.
+    ///
+    /// Neither matching is reported as
+    /// [`ReadError::InvalidData`](crate::errors::ReadError::InvalidData), as
+    /// are words wider than 8 bytes.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Endian, SliceStorage, StorageExt, errors::ReadError};
+    /// # use typenum::consts::U4;
+    /// const MAGIC: u64 = 0xCAFE_BABE;
+    ///
+    /// let mut words = [0u32; 8];
+    /// words[1] = u32::from_le_bytes(0xCAFE_BABEu32.to_be_bytes());
+    /// words[6] = 0xCAFE_BABE;
+    /// let mut storage = SliceStorage::<u32, U4>::new(&mut words);
+    ///
+    /// assert_eq!(storage.detect_endianness(1, MAGIC, MAGIC), Ok(Endian::Big));
+    /// assert_eq!(storage.detect_endianness(6, MAGIC, MAGIC), Ok(Endian::Little));
+    /// assert_eq!(storage.detect_endianness(2, MAGIC, MAGIC), Err(ReadError::InvalidData { offset: 2 }));
+    /// ```
+    fn detect_endianness(
+        &mut self,
+        word_offset: usize,
+        expected_be: u64,
+        expected_le: u64,
+    ) -> Result<Endian, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self::Word: Default,
+    {
+        use crate::errors::ReadError;
+
+        if word_offset >= self.capacity_in_words() {
+            return Err(ReadError::OutOfRange {
+                requested_offset: word_offset,
+                max_offset: self.capacity_in_words(),
+            });
+        }
+
+        let sector_size = Self::SECTOR_SIZE::to_usize();
+        let mut sector: Sector<Self> = generic_array::GenericArray::default();
+        self.read_sector(word_offset / sector_size, &mut sector)?;
+
+        let word = sector[word_offset % sector_size].to();
+        let bytes = word.as_ref();
+        if bytes.len() > 8 {
+            return Err(ReadError::InvalidData { offset: word_offset });
+        }
+
+        let be = bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+        let le = bytes.iter().rev().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+
+        if be == expected_be {
+            Ok(Endian::Big)
+        } else if le == expected_le {
+            Ok(Endian::Little)
+        } else {
+            Err(ReadError::InvalidData { offset: word_offset })
+        }
+    }
+
+    /// Reads the sector at `sector_idx`, treating
+    /// [`ReadError::Uninitialized`](crate::errors::ReadError::Uninitialized) as
+    /// success: the returned sector is filled with `fill` instead.
+    ///
+    /// This is a per-call alternative to setting an
+    /// [`UninitPolicy`](crate::UninitPolicy) on the storage medium; every
+    /// other error is passed through.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Storage, StorageExt, errors::{ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Mem([Option<[u8; 4]>; 3]);
+    /// # impl Storage for Mem {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 3 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         match self.0.get(idx) {
+    /// #             Some(Some(s)) => { buf.copy_from_slice(s); Ok(()) }
+    /// #             Some(None) => Err(ReadError::Uninitialized { offset: idx * 4 }),
+    /// #             None => Err(ReadError::OutOfRange { requested_offset: idx, max_offset: 3 }),
+    /// #         }
+    /// #     }
+    /// #     fn write_sector(&mut self, _: usize, _: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> { unimplemented!() }
+    /// # }
+    /// let mut storage = Mem([Some([1, 2, 3, 4]), None, Some([5; 4])]);
+    ///
+    /// assert_eq!(storage.read_sector_or_default(0, 0xFF), Ok(GenericArray::from([1, 2, 3, 4])));
+    /// assert_eq!(storage.read_sector_or_default(1, 0xFF), Ok(GenericArray::from([0xFF; 4])));
+    /// assert_eq!(
+    ///     storage.read_sector_or_default(3, 0xFF),
+    ///     Err(ReadError::OutOfRange { requested_offset: 3, max_offset: 3 }),
+    /// );
+    /// ```
+    fn read_sector_or_default(
+        &mut self,
+        sector_idx: usize,
+        fill: Self::Word,
+    ) -> Result<Sector<Self>, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self::Word: Clone,
+    {
+        use crate::errors::ReadError;
+        use generic_array::{GenericArray, sequence::GenericSequence};
+
+        let mut sector = GenericArray::generate(|_| fill.clone());
+
+        match self.read_sector(sector_idx, &mut sector) {
+            Ok(()) => Ok(sector),
+            // The read may have partially filled the buffer.
+            Err(ReadError::Uninitialized { .. }) => Ok(GenericArray::generate(|_| fill.clone())),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Iterates over every sector, yielding its index and whether it's erased
+    /// (i.e. every word is [`ERASED_WORD`](crate::Eraseable::ERASED_WORD));
+    /// for building a free space map at mount time.
+    ///
+    /// Sectors are read in chunks of
+    /// [`optimal_chunk_sectors`](Storage::optimal_chunk_sectors) with one
+    /// [`read_sectors`](Storage::read_sectors) call each (with `std`; one
+    /// sector at a time otherwise). If a chunk's read fails, only its first
+    /// sector is read, on its own, and the next chunk starts right after it, so
+    /// an error is reported for the sector that actually caused it. The
+    /// iterator yields that error (and then stops) if a sector can't be read.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Storage, StorageExt, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Nor([u8; 20]);
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 5 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl Eraseable for Nor {
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 20]; Ok(()) }
+    /// # }
+    /// let mut nor = Nor([0; 20]);
+    /// nor.erase().unwrap();
+    /// nor.write_sector(0, &GenericArray::from([1, 2, 3, 4])).unwrap();
+    /// nor.write_sector(2, &GenericArray::from([0xFF, 0xFF, 0xFF, 0x7F])).unwrap();
+    ///
+    /// let map: Vec<_> = nor.erase_map().map(Result::unwrap).collect();
+    /// assert_eq!(map, vec![(0, false), (1, true), (2, false), (3, true), (4, true)]);
+    /// ```
+    fn erase_map(&mut self) -> EraseMap<'_, Self>
+    where
+        Self: crate::Eraseable,
+        Self::Word: Default,
+    {
+        EraseMap {
+            chunk: chunk_buffer(self),
+            storage: self,
+            chunk_start: 0,
+            filled: 0,
+            next: 0,
+        }
+    }
+
+    /// Counts the erased and written sectors in this storage medium by
+    /// scanning it with [`erase_map`](StorageExt::erase_map); a quick way to
+    /// see how full a storage medium is.
+    ///
+    /// This reads every sector. Storage mediums that keep track of which
+    /// sectors have been written to can answer this without any reads; see
+    /// [`InitTracking::tracked_usage`](crate::InitTracking::tracked_usage).
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Storage, StorageExt, Usage, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Nor([u8; 20]);
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 5 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         buf.copy_from_slice(&self.0[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.0[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl Eraseable for Nor {
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.0 = [0xFF; 20]; Ok(()) }
+    /// # }
+    /// let mut nor = Nor([0; 20]);
+    /// nor.erase().unwrap();
+    /// nor.write_sector(1, &GenericArray::from([1, 2, 3, 4])).unwrap();
+    /// nor.write_sector(4, &GenericArray::from([0xFF, 0xFF, 0x00, 0xFF])).unwrap();
+    ///
+    /// assert_eq!(
+    ///     nor.usage(),
+    ///     Ok(Usage { total_sectors: 5, erased_sectors: 3, written_sectors: 2 }),
+    /// );
+    /// ```
+    fn usage(&mut self) -> Result<Usage, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self: crate::Eraseable,
+        Self::Word: Default,
+    {
+        let mut usage = Usage { total_sectors: self.capacity(), ..Usage::default() };
+
+        for sector in self.erase_map() {
+            if sector?.1 {
+                usage.erased_sectors += 1;
+            } else {
+                usage.written_sectors += 1;
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// Finds the first erased sector of a storage medium that's filled from
+    /// the start (i.e. a log that grows from sector 0 into erased space) with a
+    /// binary search, reading O(log n) sectors instead of all of them.
+    ///
+    /// This _assumes_ that the written sectors are contiguous: every sector
+    /// before the frontier is written and every sector after it is erased
+    /// (see [`ERASED_WORD`](crate::Eraseable::ERASED_WORD)). If that doesn't
+    /// hold, the result is _some_ written sector followed by an erased sector,
+    /// not necessarily the first one; use [`erase_map`](StorageExt::erase_map)
+    /// for storage mediums that can have holes.
+    ///
+    /// Returns the capacity if no sector is erased.
+    ///
+    /// ```rust
+    /// # use storage_traits::{Eraseable, Storage, StorageExt, errors::{EraseError, ReadError, WriteError}};
+    /// # use generic_array::GenericArray;
+    /// # use typenum::consts::U4;
+    /// # #[derive(Debug)]
+    /// # struct Nor { bytes: [u8; 4 * 20], reads: usize }
+    /// # impl Storage for Nor {
+    /// #     type Word = u8; type SECTOR_SIZE = U4; type ReadErr = (); type WriteErr = ();
+    /// #     fn capacity(&self) -> usize { 20 }
+    /// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U4>) -> Result<(), ReadError<()>> {
+    /// #         self.reads += 1;
+    /// #         buf.copy_from_slice(&self.bytes[idx * 4..][..4]); Ok(())
+    /// #     }
+    /// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U4>) -> Result<(), WriteError<()>> {
+    /// #         self.bytes[idx * 4..][..4].copy_from_slice(words); Ok(())
+    /// #     }
+    /// # }
+    /// # impl Eraseable for Nor {
+    /// #     type EraseErr = ();
+    /// #     const ERASED_WORD: u8 = 0xFF;
+    /// #     fn erase(&mut self) -> Result<(), EraseError<(), ()>> { self.bytes = [0xFF; 4 * 20]; Ok(()) }
+    /// # }
+    /// let mut nor = Nor { bytes: [0; 4 * 20], reads: 0 };
+    /// assert_eq!(nor.find_write_frontier(), Ok(20));
+    ///
+    /// nor.erase().unwrap();
+    /// assert_eq!(nor.find_write_frontier(), Ok(0));
+    ///
+    /// for idx in 0..8 {
+    ///     nor.write_sector(idx, &GenericArray::from([idx as u8; 4])).unwrap();
+    /// }
+    ///
+    /// nor.reads = 0;
+    /// assert_eq!(nor.find_write_frontier(), Ok(8));
+    /// assert!(nor.reads <= 5);
+    /// ```
+    fn find_write_frontier(&mut self) -> Result<usize, crate::errors::ReadError<Self::ReadErr>>
+    where
+        Self: crate::Eraseable,
+        Self::Word: Default,
+    {
+        let mut sector: Sector<Self> = generic_array::GenericArray::default();
+
+        // Invariant: every sector before `lo` is written and every sector from
+        // `hi` on is erased.
+        let (mut lo, mut hi) = (0, self.capacity());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            self.read_sector(mid, &mut sector)?;
+
+            if crate::extensions::is_erased::<Self>(&sector) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        Ok(lo)
+    }
+
+    using_std! {
+        /// Wraps this storage in a [`WearSim`](crate::adapters::WearSim)
+        /// adapter.
+        fn wear_sim(self, seed: u64, flip_rate: f64) -> crate::adapters::WearSim<Self>
+        where
+            Self: Sized,
+        {
+            crate::adapters::WearSim::new(self, seed, flip_rate)
+        }
+    }
+
+    #[cfg(feature = "ecc")]
+    using_std! {
+        /// Wraps this storage in an
+        /// [`EccProtected`](crate::adapters::EccProtected) adapter.
+        fn ecc_protected(self) -> Result<crate::adapters::EccProtected<Self>, crate::errors::ReadError<Self::ReadErr>>
+        where
+            Self: Sized,
+            Self::Word: Default,
+        {
+            crate::adapters::EccProtected::new(self)
+        }
+    }
+
+    #[cfg(feature = "crc")]
+    using_std! {
+        /// Checks the CRC-32 (see [`crc32`](crate::crc::crc32)) of each range
+        /// of sectors in `manifest` against the expected value, reporting
+        /// _every_ range that doesn't match.
+        ///
+        /// The bytes of each word are fed into the CRC in order, as produced by
+        /// [`AsBytes::to`]. Sectors are read one at a time.
+        ///
+        /// ```rust
+        /// # use storage_traits::{BorrowableStorage, SliceStorage, StorageExt, crc::crc32, errors::VerifyError};
+        /// # use typenum::consts::U4;
+        /// let mut words = [0u8; 4 * 6];
+        /// for (idx, w) in words.iter_mut().enumerate() { *w = idx as u8; }
+        ///
+        /// let manifest = [
+        ///     (0..2, crc32(&words[0..8])),
+        ///     (2..4, crc32(&words[8..16])),
+        ///     (4..6, crc32(&words[16..24])),
+        /// ];
+        ///
+        /// let mut storage = SliceStorage::<u8, U4>::new(&mut words);
+        /// assert_eq!(storage.verify_manifest(&manifest), Ok(()));
+        ///
+        /// storage.sector_mut(3).unwrap()[1] ^= 0xFF;
+        /// assert_eq!(storage.verify_manifest(&manifest), Err(VerifyError::Mismatch { failed: vec![1] }));
+        /// ```
+        fn verify_manifest(
+            &mut self,
+            manifest: &[(core::ops::Range<usize>, u32)],
+        ) -> Result<(), crate::errors::VerifyError<Self::ReadErr>>
+        where
+            Self::Word: Default,
+        {
+            let mut sector = generic_array::GenericArray::default();
+            let mut failed = Vec::new();
+
+            for (idx, (range, expected)) in manifest.iter().enumerate() {
+                let mut crc = crate::crc::Crc32::new();
+
+                for sector_idx in range.clone() {
+                    self.read_sector(sector_idx, &mut sector)?;
+
+                    for word in sector.iter() {
+                        crc.update(word.to().as_ref());
+                    }
+                }
+
+                if crc.finish() != *expected {
+                    failed.push(idx);
+                }
+            }
+
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(crate::errors::VerifyError::Mismatch { failed })
+            }
+        }
+    }
+
+    using_std! {
+        /// Reads the sector at `sector_idx` and decodes it as a `T`.
+        ///
+        /// ```rust
+        /// # use storage_traits::{AsBytes, SectorType, SparseStorage, StorageExt};
+        /// # use typenum::consts::U8;
+        /// #[derive(Debug, PartialEq)]
+        /// struct Header { magic: u32, version: u16, flags: u16 }
+        ///
+        /// impl AsBytes for Header {
+        ///     type To = [u8; 8];
+        ///
+        ///     fn from(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        ///         let (magic, bytes) = <u32 as AsBytes>::from(bytes)?;
+        ///         let (version, bytes) = <u16 as AsBytes>::from(bytes)?;
+        ///         let (flags, bytes) = <u16 as AsBytes>::from(bytes)?;
+        ///         Some((Header { magic, version, flags }, bytes))
+        ///     }
+        ///
+        ///     fn to(&self) -> [u8; 8] {
+        ///         let mut out = [0; 8];
+        ///         out[..4].copy_from_slice(&self.magic.to_le_bytes());
+        ///         out[4..6].copy_from_slice(&self.version.to_le_bytes());
+        ///         out[6..].copy_from_slice(&self.flags.to_le_bytes());
+        ///         out
+        ///     }
+        /// }
+        ///
+        /// impl SectorType for Header {}
+        ///
+        /// let mut storage = SparseStorage::<u8, U8>::new(4);
+        /// let header = Header { magic: 0xCAFE_F00D, version: 3, flags: 0b101 };
+        ///
+        /// storage.write_typed(0, &header).unwrap();
+        /// assert_eq!(storage.read_typed::<Header>(0).unwrap(), header);
+        /// ```
+        fn read_typed<T: SectorType>(
+            &mut self,
+            sector_idx: usize,
+        ) -> Result<T, crate::errors::ReadError<Self::ReadErr>>
+        where
+            Self::Word: Default,
+        {
+            let () = <T as FitsInSector<Self>>::FITS;
+
+            let mut sector = generic_array::GenericArray::default();
+            self.read_sector(sector_idx, &mut sector)?;
+
+            let bytes = crate::encode_words(&sector);
+            T::from(&bytes)
+                .map(|(val, _)| val)
+                .ok_or(crate::errors::ReadError::InvalidData {
+                    offset: sector_idx * Self::SECTOR_SIZE::to_usize(),
+                })
+        }
+
+        /// Encodes `value` and writes it to the sector at `sector_idx`.
+        ///
+        /// See [`read_typed`](StorageExt::read_typed).
+        fn write_typed<T: SectorType>(
+            &mut self,
+            sector_idx: usize,
+            value: &T,
+        ) -> Result<(), crate::errors::WriteError<Self::WriteErr>> {
+            let () = <T as FitsInSector<Self>>::FITS;
+
+            let bytes = value.to();
+            let (words, _) = crate::decode_words(bytes.as_ref());
+            let sector = generic_array::GenericArray::from_exact_iter(words)
+                .ok_or(crate::errors::WriteError::InvalidNumberOfBytes {
+                    bytes_given: bytes.as_ref().len(),
+                    bytes_in_a_sector: crate::storage::sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>(),
+                })?;
+
+            self.write_sector(sector_idx, &sector)
+        }
+
+        /// Streams the contents of `reader` into the storage medium, one sector
+        /// at a time, starting at the sector at `start_sector`.
+        ///
+        /// Stops at the end of `reader`; if it ends partway through a sector
+        /// the rest of that sector is filled with zeros. Returns the number of
+        /// sectors that were written. Readers that return fewer bytes than
+        /// asked for (or that are interrupted) are fine.
+        ///
+        /// ```rust
+        /// # use storage_traits::{SparseStorage, Storage, StorageExt};
+        /// # use generic_array::GenericArray;
+        /// # use std::io::Read;
+        /// # use typenum::consts::U4;
+        /// // Hands out at most 3 bytes at a time.
+        /// struct Trickle<'a>(&'a [u8]);
+        ///
+        /// impl Read for Trickle<'_> {
+        ///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        ///         let len = buf.len().min(3).min(self.0.len());
+        ///         buf[..len].copy_from_slice(&self.0[..len]);
+        ///         self.0 = &self.0[len..];
+        ///         Ok(len)
+        ///     }
+        /// }
+        ///
+        /// let mut storage = SparseStorage::<u8, U4>::new(8);
+        /// let source: Vec<u8> = (1..=10).collect();
+        ///
+        /// assert_eq!(storage.write_from_reader(1, &mut Trickle(&source)).unwrap(), 3);
+        ///
+        /// let mut buf = GenericArray::default();
+        /// storage.read_sector(2, &mut buf).unwrap();
+        /// assert_eq!(buf, GenericArray::from([5, 6, 7, 8]));
+        /// storage.read_sector(3, &mut buf).unwrap();
+        /// assert_eq!(buf, GenericArray::from([9, 10, 0, 0]));
+        /// assert!(storage.read_sector(4, &mut buf).is_err());
+        /// ```
+        fn write_from_reader<R: std::io::Read + ?Sized>(
+            &mut self,
+            start_sector: usize,
+            reader: &mut R,
+        ) -> Result<usize, crate::errors::StreamError<crate::errors::WriteError<Self::WriteErr>>> {
+            use crate::errors::{StreamError, WriteError};
+            use std::io::ErrorKind;
+
+            let sector_size_in_bytes = crate::storage::sector_size_in_bytes::<Self::Word, Self::SECTOR_SIZE>();
+            let mut bytes = vec![0; sector_size_in_bytes];
+            let mut sectors_written = 0;
+
+            loop {
+                let mut filled = 0;
+                while filled < bytes.len() {
+                    match reader.read(&mut bytes[filled..]) {
+                        Ok(0) => break,
+                        Ok(len) => filled += len,
+                        Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                        Err(err) => return Err(StreamError::Io(err)),
+                    }
+                }
+
+                if filled == 0 {
+                    return Ok(sectors_written);
+                }
+
+                for byte in bytes[filled..].iter_mut() {
+                    *byte = 0;
+                }
+
+                let (words, _) = crate::decode_words(&bytes);
+                let sector = generic_array::GenericArray::from_exact_iter(words)
+                    .ok_or(StreamError::Storage(WriteError::InvalidNumberOfBytes {
+                        bytes_given: bytes.len(),
+                        bytes_in_a_sector: sector_size_in_bytes,
+                    }))?;
+
+                self.write_sector(start_sector + sectors_written, &sector)
+                    .map_err(StreamError::Storage)?;
+                sectors_written += 1;
+
+                if filled < bytes.len() {
+                    return Ok(sectors_written);
+                }
+            }
+        }
+
+        /// Streams `len_sectors` sectors, starting at the sector at
+        /// `start_sector`, out to `writer`; the counterpart to
+        /// [`write_from_reader`](StorageExt::write_from_reader).
+        ///
+        /// The whole range is checked against the capacity before anything is
+        /// read or written.
+        ///
+        /// ```rust
+        /// # use storage_traits::{SparseStorage, Storage, StorageExt, errors::{ReadError, StreamError}};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U2;
+        /// let mut storage = SparseStorage::<u16, U2>::new(4);
+        /// for idx in 0..4 {
+        ///     storage.write_sector(idx, &GenericArray::from([idx as u16, 0x0100])).unwrap();
+        /// }
+        ///
+        /// let mut out = Vec::new();
+        /// storage.read_into_writer(1, 3, &mut out).unwrap();
+        /// assert_eq!(out, vec![1, 0, 0, 1, 2, 0, 0, 1, 3, 0, 0, 1]);
+        ///
+        /// assert!(matches!(
+        ///     storage.read_into_writer(2, 3, &mut out),
+        ///     Err(StreamError::Storage(ReadError::OutOfRange { requested_offset: 4, max_offset: 4 })),
+        /// ));
+        /// ```
+        fn read_into_writer<W: std::io::Write + ?Sized>(
+            &mut self,
+            start_sector: usize,
+            len_sectors: usize,
+            writer: &mut W,
+        ) -> Result<(), crate::errors::StreamError<crate::errors::ReadError<Self::ReadErr>>>
+        where
+            Self::Word: Default,
+        {
+            use crate::errors::{ReadError, StreamError};
+
+            match start_sector.checked_add(len_sectors) {
+                Some(end) if end <= self.capacity() => {},
+                _ => return Err(StreamError::Storage(ReadError::OutOfRange {
+                    requested_offset: crate::errors::last_offset(start_sector, len_sectors),
+                    max_offset: self.capacity(),
+                })),
+            }
+
+            let mut sector = generic_array::GenericArray::default();
+            for sector_idx in start_sector..(start_sector + len_sectors) {
+                self.read_sector(sector_idx, &mut sector).map_err(StreamError::Storage)?;
+                writer.write_all(&crate::encode_words(&sector))?;
+            }
+
+            Ok(())
+        }
+
+        /// Writes the entire contents of this storage medium to `out` as a
+        /// flat raw image: exactly [`capacity_in_bytes`](Storage::capacity_in_bytes)
+        /// bytes, with each word in little endian order (as encoded by
+        /// [`AsBytes::to`]).
+        ///
+        /// There's no header or metadata of any kind (not even the sector
+        /// size), so the output can be handed to tools that expect raw disk
+        /// images (i.e. emulators like QEMU). Storage errors are reported as
+        /// [`std::io::Error`]s of kind
+        /// [`Other`](std::io::ErrorKind::Other).
+        ///
+        /// ```rust
+        /// # use storage_traits::{FileBackedStorage, Storage, StorageExt};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// # let path = std::env::temp_dir().join("storage-traits-export-raw.bin");
+        /// # let _ = std::fs::remove_file(&path);
+        /// let mut storage = FileBackedStorage::<u32, U4>::new(&path, 3).unwrap();
+        /// storage.write_sector(1, &GenericArray::from([0x0102_0304, 5, 6, 7])).unwrap();
+        ///
+        /// let mut image = Vec::new();
+        /// storage.export_raw(&mut image).unwrap();
+        ///
+        /// assert_eq!(image.len(), storage.capacity_in_bytes());
+        /// assert_eq!(image, std::fs::read(&path).unwrap());
+        /// assert_eq!(image[16..20], [4, 3, 2, 1]);
+        /// # std::fs::remove_file(&path).unwrap();
+        /// ```
+        fn export_raw<W: std::io::Write + ?Sized>(&mut self, out: &mut W) -> std::io::Result<()>
+        where
+            Self::Word: Default,
+        {
+            use crate::errors::StreamError;
+
+            let capacity = self.capacity();
+            match self.read_into_writer(0, capacity, out) {
+                Ok(()) => Ok(()),
+                Err(StreamError::Io(err)) => Err(err),
+                Err(StreamError::Storage(err)) => Err(std::io::Error::other(format!("{:?}", err))),
+            }
+        }
+
+        /// Reads the sector at `sector_idx` into `buf`, resizing it to hold
+        /// exactly one sector.
+        ///
+        /// `buf`'s allocation is reused so scans over many sectors only
+        /// allocate once:
+        ///
+        /// ```rust
+        /// # use storage_traits::{SparseStorage, Storage, StorageExt};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U4;
+        /// let mut storage = SparseStorage::<u16, U4>::new(3);
+        /// for idx in 0..3 {
+        ///     storage.write_sector(idx, &GenericArray::from([idx as u16; 4])).unwrap();
+        /// }
+        ///
+        /// let mut buf = Vec::new();
+        /// for idx in 0..3 {
+        ///     storage.read_sector_into_vec(idx, &mut buf).unwrap();
+        ///     assert_eq!(buf, vec![idx as u16; 4]);
+        /// }
+        /// ```
+        fn read_sector_into_vec(
+            &mut self,
+            sector_idx: usize,
+            buf: &mut Vec<Self::Word>,
+        ) -> Result<(), crate::errors::ReadError<Self::ReadErr>>
+        where
+            Self::Word: Default + Clone,
+        {
+            let mut sector = generic_array::GenericArray::default();
+            self.read_sector(sector_idx, &mut sector)?;
+
+            buf.clear();
+            buf.extend_from_slice(&sector);
+
+            Ok(())
+        }
+
+        /// Reads the sector at `sector_idx` and decodes its bytes with
+        /// `decode`, `record_len` bytes at a time, for ad-hoc formats that
+        /// aren't worth an [`AsBytes`] impl.
+        ///
+        /// This is only for byte backends (`Word = u8`). Bytes at the end of
+        /// the sector that don't make up a whole record are ignored; a
+        /// `record_len` of 0 decodes nothing.
+        ///
+        /// ```rust
+        /// # use storage_traits::{SparseStorage, Storage, StorageExt};
+        /// # use generic_array::GenericArray;
+        /// # use typenum::consts::U8;
+        /// #[derive(Debug, PartialEq)]
+        /// struct Entry { id: u8, len: u16 }
+        ///
+        /// let mut storage = SparseStorage::<u8, U8>::new(2);
+        /// storage.write_sector(1, &GenericArray::from([1, 0x00, 0x02, 2, 0x12, 0x34, 0xFF, 0xFF])).unwrap();
+        ///
+        /// let entries = storage.read_sector_with(1, 3, |bytes| Entry {
+        ///     id: bytes[0],
+        ///     len: u16::from_be_bytes([bytes[1], bytes[2]]),
+        /// }).unwrap();
+        ///
+        /// assert_eq!(entries, vec![Entry { id: 1, len: 2 }, Entry { id: 2, len: 0x1234 }]);
+        /// ```
+        fn read_sector_with<T, F: FnMut(&[u8]) -> T>(
+            &mut self,
+            sector_idx: usize,
+            record_len: usize,
+            decode: F,
+        ) -> Result<Vec<T>, crate::errors::ReadError<Self::ReadErr>>
+        where
+            Self: Storage<Word = u8>,
+        {
+            let mut sector = generic_array::GenericArray::default();
+            self.read_sector(sector_idx, &mut sector)?;
+
+            if record_len == 0 {
+                return Ok(Vec::new());
+            }
+
+            Ok(sector.chunks_exact(record_len).map(decode).collect())
+        }
+    }
+}
+
+impl<S: Storage + ?Sized> StorageExt for S {}
+
+/// A byte order; see [`StorageExt::detect_endianness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// How many of a storage medium's sectors are in use; see
+/// [`StorageExt::usage`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Usage {
+    /// The number of sectors in the storage medium.
+    pub total_sectors: usize,
+    /// The number of sectors that are erased (or, for storage mediums that
+    /// track it, that have never been written to).
+    pub erased_sectors: usize,
+    /// The number of sectors that hold data.
+    pub written_sectors: usize,
+}
+
+/// Iterator over whether each sector of a storage medium is erased; see
+/// [`StorageExt::erase_map`].
+pub struct EraseMap<'a, S: crate::Eraseable + ?Sized> {
+    storage: &'a mut S,
+    // Sectors `chunk_start..(chunk_start + filled)`, read ahead.
+    chunk: ChunkBuffer<S>,
+    chunk_start: usize,
+    filled: usize,
+    next: usize,
+}
+
+impl<S: crate::Eraseable + core::fmt::Debug + ?Sized> core::fmt::Debug for EraseMap<'_, S>
+where
+    S::Word: core::fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.debug_struct("EraseMap")
+            .field("storage", &self.storage)
+            .field("chunk_start", &self.chunk_start)
+            .field("filled", &self.filled)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<S: crate::Eraseable + ?Sized> Iterator for EraseMap<'_, S> {
+    type Item = Result<(usize, bool), crate::errors::ReadError<S::ReadErr>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.storage.capacity() {
+            return None;
+        }
+
+        let sector_idx = self.next;
+        if sector_idx >= self.chunk_start + self.filled {
+            match read_ahead(self.storage, sector_idx, &mut self.chunk) {
+                Ok(count) => {
+                    self.chunk_start = sector_idx;
+                    self.filled = count;
+                }
+                Err(err) => {
+                    self.next = usize::MAX;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        self.next += 1;
+        let sector = &self.chunk[sector_idx - self.chunk_start];
+        Some(Ok((sector_idx, crate::extensions::is_erased::<S>(sector))))
+    }
+}
@@ -0,0 +1,294 @@
+//! A conformance suite for [`Storage`] implementations.
+//!
+//! Downstream backends can run [`conformance`] (i.e. from a test) to check that
+//! they uphold the invariants the rest of this crate (and its users) rely on;
+//! [`Eraseable`] backends can also run [`conformance_eraseable`].
+//!
+//! Requires that `no_std` is not enabled.
+
+#![allow(deprecated)] // generic-array 0.14; see the note in lib.rs
+
+use crate::{decode_words, Eraseable, Storage};
+use crate::bytes::bytes_eq;
+use crate::errors::{ReadError, WriteError};
+
+use core::fmt::Debug;
+
+use generic_array::GenericArray;
+use typenum::marker_traits::Unsigned;
+
+/// The capacities (in sectors) that [`conformance`] asks for.
+pub const CAPACITIES: &[usize] = &[1, 2, 5];
+
+/// Runs a battery of checks against the [`Storage`] implementations that
+/// `make` produces; `make` is called with a capacity (in sectors) and should
+/// return a fresh, empty storage medium with that many sectors.
+///
+/// Panics with a message describing the violation if a check fails. For every
+/// capacity in [`CAPACITIES`], this checks that:
+///   - [`capacity`](Storage::capacity) is the capacity that was asked for and
+///     [`capacity_in_words`](Storage::capacity_in_words)/
+///     [`capacity_in_bytes`](Storage::capacity_in_bytes) agree with it
+///   - every sector reads back what was last written to it
+///   - writing a sector doesn't change any other sector
+///   - reads and writes of sectors at or past the capacity fail with
+///     `OutOfRange` errors (and reads leave the buffer untouched)
+///   - zero-length [`write_sectors`](Storage::write_sectors) calls succeed at
+///     any index up to and including the capacity
+///
+/// Sectors are never read before they're written to so backends that error
+/// on reads of uninitialized sectors are fine. Extension traits aren't covered;
+/// see [`conformance_eraseable`] for erasing.
+///
+/// The crate's own backends pass:
+///
+/// ```rust
+/// # use storage_traits::{FileBackedStorage, RopeStorage, SliceStorage, SparseStorage};
+/// # use storage_traits::testing::conformance;
+/// # use typenum::consts::{U4, U16};
+/// conformance(SparseStorage::<u8, U16>::new);
+/// conformance(RopeStorage::<u32, U4>::new);
+/// conformance(|sectors| SliceStorage::<u16, U4>::new(Box::leak(vec![0; sectors * 4].into_boxed_slice())));
+///
+/// let dir = std::env::temp_dir();
+/// let mut files = 0;
+/// conformance(|sectors| {
+///     files += 1;
+///     let path = dir.join(format!("storage-traits-conformance-{}.bin", files));
+///     let _ = std::fs::remove_file(&path);
+///     FileBackedStorage::<u16, U16>::new(&path, sectors).unwrap()
+/// });
+/// # for idx in 1..=files {
+/// #     std::fs::remove_file(dir.join(format!("storage-traits-conformance-{}.bin", idx))).unwrap();
+/// # }
+/// ```
+pub fn conformance<S, F>(mut make: F)
+where
+    S: Storage,
+    S::Word: Default + Debug,
+    F: FnMut(usize) -> S,
+{
+    for &capacity in CAPACITIES {
+        let mut storage = make(capacity);
+
+        check_geometry(&storage, capacity);
+        check_roundtrips(&mut storage);
+        check_isolation(&mut storage);
+        check_bounds(&mut storage);
+        check_empty_writes(&mut storage);
+    }
+}
+
+/// Checks [`Eraseable`] implementations the same way [`conformance`] checks
+/// [`Storage`] implementations; `make` is called with a capacity (in sectors)
+/// and should return a fresh storage medium with that many sectors.
+///
+/// Panics with a message describing the violation if a check fails. For every
+/// capacity in [`CAPACITIES`], this fills every sector with non-erased data,
+/// calls [`erase`](Eraseable::erase) and then checks that:
+///   - every word of every sector reads back as
+///     [`ERASED_WORD`](Eraseable::ERASED_WORD) (compared byte for byte)
+///   - [`verify_erased`](Eraseable::verify_erased) agrees
+///
+/// ```rust
+/// # use storage_traits::{Eraseable, SparseStorage, Storage, errors::{EraseError, ReadError, WriteError}};
+/// # use storage_traits::testing::conformance_eraseable;
+/// # use generic_array::GenericArray;
+/// # use typenum::consts::U16;
+/// // A NOR flash-like medium: erasing sets every byte to 0xFF.
+/// #[derive(Debug)]
+/// struct Nor(SparseStorage<u8, U16>);
+///
+/// impl Storage for Nor {
+///     // ...
+/// #     type Word = u8; type SECTOR_SIZE = U16; type ReadErr = std::convert::Infallible; type WriteErr = std::convert::Infallible;
+/// #     fn capacity(&self) -> usize { self.0.capacity() }
+/// #     fn read_sector(&mut self, idx: usize, buf: &mut GenericArray<u8, U16>) -> Result<(), ReadError<Self::ReadErr>> {
+/// #         self.0.read_sector(idx, buf)
+/// #     }
+/// #     fn write_sector(&mut self, idx: usize, words: &GenericArray<u8, U16>) -> Result<(), WriteError<Self::WriteErr>> {
+/// #         self.0.write_sector(idx, words)
+/// #     }
+/// }
+///
+/// impl Eraseable for Nor {
+///     type EraseErr = ();
+///     const ERASED_WORD: u8 = 0xFF;
+///
+///     fn erase(&mut self) -> Result<(), EraseError<Self::WriteErr, ()>> {
+///         for idx in 0..self.capacity() {
+///             self.write_sector(idx, &GenericArray::from([0xFF; 16]))?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// conformance_eraseable(|sectors| Nor(SparseStorage::new(sectors)));
+/// ```
+pub fn conformance_eraseable<S, F>(mut make: F)
+where
+    S: Eraseable,
+    S::Word: Default + Debug,
+    F: FnMut(usize) -> S,
+{
+    for &capacity in CAPACITIES {
+        let mut storage = make(capacity);
+
+        check_erase(&mut storage);
+    }
+}
+
+fn pattern<S: Storage>(sector_idx: usize, generation: u8) -> GenericArray<S::Word, S::SECTOR_SIZE> {
+    let bytes: Vec<u8> = (0..S::sector_size_in_bytes())
+        .map(|idx| (sector_idx as u8).wrapping_mul(31) ^ (idx as u8).wrapping_mul(7) ^ generation)
+        .collect();
+
+    let (words, _) = decode_words(&bytes);
+    GenericArray::from_exact_iter(words)
+        .expect("`AsBytes::from` couldn't decode the test pattern")
+}
+
+fn read<S: Storage>(storage: &mut S, sector_idx: usize) -> GenericArray<S::Word, S::SECTOR_SIZE>
+where
+    S::Word: Default,
+{
+    let mut buffer = GenericArray::default();
+    if let Err(err) = storage.read_sector(sector_idx, &mut buffer) {
+        panic!("reading sector {} (of {}) failed: {:?}", sector_idx, storage.capacity(), err);
+    }
+
+    buffer
+}
+
+fn write<S: Storage>(storage: &mut S, sector_idx: usize, sector: &GenericArray<S::Word, S::SECTOR_SIZE>) {
+    if let Err(err) = storage.write_sector(sector_idx, sector) {
+        panic!("writing sector {} (of {}) failed: {:?}", sector_idx, storage.capacity(), err);
+    }
+}
+
+fn check_geometry<S: Storage>(storage: &S, capacity: usize) {
+    assert_eq!(
+        storage.capacity(), capacity,
+        "`capacity` doesn't match the capacity that was asked for",
+    );
+    assert_eq!(
+        storage.capacity_in_words(), capacity * S::SECTOR_SIZE::to_usize(),
+        "`capacity_in_words` isn't `capacity` sectors' worth of words",
+    );
+    assert_eq!(
+        storage.capacity_in_bytes(), capacity * S::sector_size_in_bytes(),
+        "`capacity_in_bytes` isn't `capacity` sectors' worth of bytes",
+    );
+}
+
+fn check_roundtrips<S: Storage>(storage: &mut S)
+where
+    S::Word: Default,
+{
+    for generation in [0x00, 0xFF] {
+        for sector_idx in 0..storage.capacity() {
+            write(storage, sector_idx, &pattern::<S>(sector_idx, generation));
+        }
+
+        for sector_idx in 0..storage.capacity() {
+            assert!(
+                bytes_eq(&read(storage, sector_idx), &pattern::<S>(sector_idx, generation)),
+                "sector {} didn't read back what was last written to it", sector_idx,
+            );
+        }
+    }
+}
+
+fn check_isolation<S: Storage>(storage: &mut S)
+where
+    S::Word: Default,
+{
+    // Every sector currently holds generation 0xFF (see `check_roundtrips`).
+    for written in 0..storage.capacity() {
+        write(storage, written, &pattern::<S>(written, 0x5A));
+
+        for other in (0..storage.capacity()).filter(|idx| *idx != written) {
+            assert!(
+                bytes_eq(&read(storage, other), &pattern::<S>(other, 0xFF)),
+                "writing sector {} changed sector {}", written, other,
+            );
+        }
+
+        write(storage, written, &pattern::<S>(written, 0xFF));
+    }
+}
+
+fn check_bounds<S: Storage>(storage: &mut S)
+where
+    S::Word: Default,
+{
+    let capacity = storage.capacity();
+
+    for sector_idx in [capacity, capacity + 1, usize::MAX] {
+        let mut buffer = pattern::<S>(0, 0xA5);
+        match storage.read_sector(sector_idx, &mut buffer) {
+            Err(ReadError::OutOfRange { .. }) => {},
+            other => panic!(
+                "reading sector {} (of {}) should fail with `OutOfRange`; got {:?}",
+                sector_idx, capacity, other,
+            ),
+        }
+        assert!(
+            bytes_eq(&buffer, &pattern::<S>(0, 0xA5)),
+            "reading sector {} (of {}) failed but still modified the buffer", sector_idx, capacity,
+        );
+
+        match storage.write_sector(sector_idx, &pattern::<S>(0, 0xA5)) {
+            Err(WriteError::OutOfRange { .. }) => {},
+            other => panic!(
+                "writing sector {} (of {}) should fail with `OutOfRange`; got {:?}",
+                sector_idx, capacity, other,
+            ),
+        }
+    }
+}
+
+fn check_empty_writes<S: Storage>(storage: &mut S) {
+    for sector_idx in 0..=storage.capacity() {
+        if let Err(err) = storage.write_sectors(sector_idx, &[]) {
+            panic!(
+                "writing zero sectors at {} (of {}) should succeed; got {:?}",
+                sector_idx, storage.capacity(), err,
+            );
+        }
+    }
+}
+
+fn check_erase<S: Eraseable>(storage: &mut S)
+where
+    S::Word: Default + Debug,
+{
+    // So that an `erase` that does nothing can't pass.
+    for sector_idx in 0..storage.capacity() {
+        write(storage, sector_idx, &pattern::<S>(sector_idx, 0x5A));
+    }
+
+    if let Err(err) = storage.erase() {
+        panic!("erasing (with {} sectors) failed: {:?}", storage.capacity(), err);
+    }
+
+    let erased = S::ERASED_WORD;
+    for sector_idx in 0..storage.capacity() {
+        let sector = read(storage, sector_idx);
+        let word_idx = sector
+            .iter()
+            .position(|word| !bytes_eq(core::slice::from_ref(word), core::slice::from_ref(&erased)));
+
+        if let Some(word_idx) = word_idx {
+            panic!(
+                "word {} of sector {} is {:?} after an erase instead of `ERASED_WORD` ({:?})",
+                word_idx, sector_idx, sector[word_idx], erased,
+            );
+        }
+    }
+
+    match storage.verify_erased() {
+        Ok(true) => {},
+        other => panic!("`verify_erased` should be `Ok(true)` after an erase; got {:?}", other),
+    }
+}